@@ -0,0 +1,217 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::atomic::{self, AtomicBool};
+
+// A maximization problem to be solved by branch and bound. To minimize
+// something, maximize its negation (e.g. with std::cmp::Reverse).
+pub trait Problem {
+    type State;
+    type Move;
+    type Value: Ord + Copy;
+
+    // The moves that can be made from a state. A dead end has no moves.
+    fn moves(&self, state: &Self::State) -> Vec<Self::Move>;
+
+    fn apply(&self, state: &Self::State, mv: &Self::Move) -> Self::State;
+
+    // The value of stopping at this state, or None if stopping here isn't a
+    // valid solution
+    fn objective(&self, state: &Self::State) -> Option<Self::Value>;
+
+    // An upper bound on the objective of this state and every state reachable
+    // from it. States that can't beat the best solution so far are pruned.
+    fn bound(&self, state: &Self::State) -> Self::Value;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchOrder {
+    // Uses little memory
+    DepthFirst,
+    // Expands the state with the highest bound first
+    BestFirst,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    // States whose objective and bound were evaluated
+    pub nodes: usize,
+    // States which were not expanded because of their bound
+    pub pruned: usize,
+    // How many times the best solution improved
+    pub improvements: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Outcome<V> {
+    pub best: Option<V>,
+    pub stats: SearchStats,
+    // False if the search was cancelled or hit its node limit, in which case
+    // `best` may not be optimal
+    pub is_complete: bool,
+}
+
+pub struct BranchAndBound<'a, P> {
+    problem: &'a P,
+    order: SearchOrder,
+    max_nodes: Option<usize>,
+    cancel_flag: Option<&'a AtomicBool>,
+}
+
+// A state waiting in the best-first queue, ordered by its bound alone
+struct Queued<V, S> {
+    bound: V,
+    state: S,
+}
+
+impl<V: Ord, S> PartialEq for Queued<V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bound == other.bound
+    }
+}
+
+impl<V: Ord, S> Eq for Queued<V, S> {}
+
+impl<V: Ord, S> PartialOrd for Queued<V, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<V: Ord, S> Ord for Queued<V, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.bound.cmp(&other.bound)
+    }
+}
+
+impl<'a, P: Problem> BranchAndBound<'a, P> {
+    pub fn new(problem: &'a P) -> Self {
+        Self {
+            problem,
+            order: SearchOrder::DepthFirst,
+            max_nodes: None,
+            cancel_flag: None,
+        }
+    }
+
+    pub fn with_order(self, order: SearchOrder) -> Self {
+        Self { order, ..self }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_max_nodes(self, max_nodes: usize) -> Self {
+        Self {
+            max_nodes: Some(max_nodes),
+            ..self
+        }
+    }
+
+    // The search stops soon after the flag is set, e.g. from another thread
+    #[allow(dead_code)]
+    pub fn with_cancel_flag(self, cancel_flag: &'a AtomicBool) -> Self {
+        Self {
+            cancel_flag: Some(cancel_flag),
+            ..self
+        }
+    }
+
+    pub fn maximize(&self, start: P::State) -> Outcome<P::Value> {
+        let mut outcome = Outcome {
+            best: None,
+            stats: SearchStats::default(),
+            is_complete: true,
+        };
+        match self.order {
+            SearchOrder::DepthFirst => self.depth_first(start, &mut outcome),
+            SearchOrder::BestFirst => self.best_first(start, &mut outcome),
+        }
+        outcome
+    }
+
+    fn should_stop(&self, stats: &SearchStats) -> bool {
+        self.max_nodes
+            .is_some_and(|max_nodes| stats.nodes >= max_nodes)
+            || self
+                .cancel_flag
+                .is_some_and(|flag| flag.load(atomic::Ordering::Relaxed))
+    }
+
+    fn can_improve(bound: P::Value, outcome: &Outcome<P::Value>) -> bool {
+        outcome.best.is_none_or(|best| bound > best)
+    }
+
+    // Records the state's objective, and returns its bound if it is still
+    // worth expanding
+    fn visit(
+        &self,
+        state: &P::State,
+        outcome: &mut Outcome<P::Value>,
+    ) -> Option<P::Value> {
+        outcome.stats.nodes += 1;
+        if let Some(value) = self.problem.objective(state)
+            && Self::can_improve(value, outcome)
+        {
+            outcome.best = Some(value);
+            outcome.stats.improvements += 1;
+        }
+        let bound = self.problem.bound(state);
+        if Self::can_improve(bound, outcome) {
+            Some(bound)
+        } else {
+            outcome.stats.pruned += 1;
+            None
+        }
+    }
+
+    fn depth_first(&self, start: P::State, outcome: &mut Outcome<P::Value>) {
+        let mut stack = Vec::new();
+        if self.visit(&start, outcome).is_some() {
+            let moves = self.problem.moves(&start).into_iter();
+            stack.push((start, moves));
+        }
+        while let Some((state, moves)) = stack.last_mut() {
+            if self.should_stop(&outcome.stats) {
+                outcome.is_complete = false;
+                return;
+            }
+            let Some(mv) = moves.next() else {
+                stack.pop();
+                continue;
+            };
+            let child = self.problem.apply(state, &mv);
+            if self.visit(&child, outcome).is_some() {
+                let moves = self.problem.moves(&child).into_iter();
+                stack.push((child, moves));
+            }
+        }
+    }
+
+    fn best_first(&self, start: P::State, outcome: &mut Outcome<P::Value>) {
+        let mut queue = BinaryHeap::new();
+        if let Some(bound) = self.visit(&start, outcome) {
+            queue.push(Queued {
+                bound,
+                state: start,
+            });
+        }
+        while let Some(Queued { bound, state }) = queue.pop() {
+            // Every other queued state has a bound no higher than this one
+            if !Self::can_improve(bound, outcome) {
+                outcome.stats.pruned += 1 + queue.len();
+                return;
+            }
+            for mv in self.problem.moves(&state) {
+                if self.should_stop(&outcome.stats) {
+                    outcome.is_complete = false;
+                    return;
+                }
+                let child = self.problem.apply(&state, &mv);
+                if let Some(bound) = self.visit(&child, outcome) {
+                    queue.push(Queued {
+                        bound,
+                        state: child,
+                    });
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,12 @@
 use std::cmp::{max, min};
+use std::collections::VecDeque;
 use std::io;
 use std::io::BufRead;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
 
 use crate::errors::invalid_input;
+use crate::parse::parse_all;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Position {
@@ -10,6 +14,92 @@ pub struct Position {
     pub col: isize,
 }
 
+impl Add for Position {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            row: self.row + other.row,
+            col: self.col + other.col,
+        }
+    }
+}
+
+impl AddAssign for Position {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Position {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            row: self.row - other.row,
+            col: self.col - other.col,
+        }
+    }
+}
+
+impl SubAssign for Position {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<isize> for Position {
+    type Output = Self;
+
+    fn mul(self, scalar: isize) -> Self {
+        Self {
+            row: self.row * scalar,
+            col: self.col * scalar,
+        }
+    }
+}
+
+impl Neg for Position {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            row: -self.row,
+            col: -self.col,
+        }
+    }
+}
+
+// Parses "x,y", where x is the column and y is the row
+impl FromStr for Position {
+    type Err = io::Error;
+
+    fn from_str(string: &str) -> io::Result<Self> {
+        let &[col, row] =
+            &parse_all::<_, isize>(string.split(',').map(str::trim))?[..]
+        else {
+            return Err(invalid_input("Expected x,y"));
+        };
+        Ok(Self { row, col })
+    }
+}
+
+impl Position {
+    pub fn manhattan_distance(self, other: Self) -> isize {
+        (self.row - other.row).abs() + (self.col - other.col).abs()
+    }
+
+    #[allow(dead_code)]
+    pub fn chebyshev_distance(self, other: Self) -> isize {
+        max((self.row - other.row).abs(), (self.col - other.col).abs())
+    }
+
+    #[allow(dead_code)]
+    pub fn squared_distance(self, other: Self) -> isize {
+        (self.row - other.row).pow(2) + (self.col - other.col).pow(2)
+    }
+}
+
 impl Position {
     pub fn straight_line_to(
         self,
@@ -61,6 +151,7 @@ impl Direction {
     #[allow(dead_code)]
     pub const ALL: [Self; 4] = [Self::Up, Self::Down, Self::Left, Self::Right];
 
+    #[allow(dead_code)]
     pub fn turn_direction(self, other: Self) -> Self {
         match (self, other) {
             (Self::Up, Self::Up)
@@ -82,6 +173,7 @@ impl Direction {
         }
     }
 
+    #[allow(dead_code)]
     pub fn turn_right(self) -> Self {
         match self {
             Self::Up => Self::Right,
@@ -91,6 +183,7 @@ impl Direction {
         }
     }
 
+    #[allow(dead_code)]
     pub fn turn_left(self) -> Self {
         match self {
             Self::Up => Self::Left,
@@ -101,6 +194,90 @@ impl Direction {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction8 {
+    Up = 0,
+    UpRight = 1,
+    Right = 2,
+    DownRight = 3,
+    Down = 4,
+    DownLeft = 5,
+    Left = 6,
+    UpLeft = 7,
+}
+
+impl Direction8 {
+    // In clockwise order, starting from Up
+    pub const ALL: [Self; 8] = [
+        Self::Up,
+        Self::UpRight,
+        Self::Right,
+        Self::DownRight,
+        Self::Down,
+        Self::DownLeft,
+        Self::Left,
+        Self::UpLeft,
+    ];
+
+    fn from_index(index: usize) -> Self {
+        Self::ALL[index % 8]
+    }
+
+    // Rotates clockwise by 45 degrees
+    #[allow(dead_code)]
+    pub fn rotate_right(self) -> Self {
+        Self::from_index(self as usize + 1)
+    }
+
+    // Rotates counterclockwise by 45 degrees
+    #[allow(dead_code)]
+    pub fn rotate_left(self) -> Self {
+        Self::from_index(self as usize + 7)
+    }
+
+    pub fn opposite(self) -> Self {
+        Self::from_index(self as usize + 4)
+    }
+
+    #[allow(dead_code)]
+    pub fn is_diagonal(self) -> bool {
+        self as usize % 2 == 1
+    }
+
+    // Returns the change in (row, col) from moving one step in this direction
+    pub fn delta(self) -> (isize, isize) {
+        match self {
+            Self::Up => (-1, 0),
+            Self::UpRight => (-1, 1),
+            Self::Right => (0, 1),
+            Self::DownRight => (1, 1),
+            Self::Down => (1, 0),
+            Self::DownLeft => (1, -1),
+            Self::Left => (0, -1),
+            Self::UpLeft => (-1, -1),
+        }
+    }
+
+    pub fn move_n(self, position: Position, steps: isize) -> Position {
+        let (row_delta, col_delta) = self.delta();
+        Position {
+            row: position.row + row_delta * steps,
+            col: position.col + col_delta * steps,
+        }
+    }
+}
+
+impl From<Direction> for Direction8 {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Up => Self::Up,
+            Direction::Down => Self::Down,
+            Direction::Left => Self::Left,
+            Direction::Right => Self::Right,
+        }
+    }
+}
+
 impl Position {
     pub fn move_one(self, direction: Direction) -> Self {
         let row = self.row;
@@ -114,16 +291,7 @@ impl Position {
     }
 
     pub fn eight_neighbors(self) -> [Self; 8] {
-        [
-            self.move_one(Direction::Up).move_one(Direction::Left),
-            self.move_one(Direction::Up),
-            self.move_one(Direction::Up).move_one(Direction::Right),
-            self.move_one(Direction::Left),
-            self.move_one(Direction::Right),
-            self.move_one(Direction::Down).move_one(Direction::Left),
-            self.move_one(Direction::Down),
-            self.move_one(Direction::Down).move_one(Direction::Right),
-        ]
+        Direction8::ALL.map(|direction| direction.move_n(self, 1))
     }
 
     #[allow(dead_code)]
@@ -143,6 +311,16 @@ pub trait Cell: Copy {
     fn from_char(ch: char) -> Option<Self>;
 }
 
+impl Cell for char {
+    fn to_char(self) -> char {
+        self
+    }
+
+    fn from_char(ch: char) -> Option<Self> {
+        Some(ch)
+    }
+}
+
 pub struct CellMap<C> {
     layout: Vec<Vec<C>>,
     width: isize,
@@ -187,12 +365,10 @@ impl<C: Cell> CellMap<C> {
 }
 
 impl<C> CellMap<C> {
-    #[allow(dead_code)]
     pub fn width(&self) -> isize {
         self.width
     }
 
-    #[allow(dead_code)]
     pub fn height(&self) -> isize {
         self.height
     }
@@ -210,6 +386,18 @@ impl<C> CellMap<C> {
             && position.col < self.width
     }
 
+    // Returns the positions from `start` (inclusive) to the edge of the map,
+    // moving in `direction`
+    pub fn ray(
+        &self,
+        start: Position,
+        direction: Direction8,
+    ) -> impl Iterator<Item = Position> {
+        (0..)
+            .map(move |steps| direction.move_n(start, steps))
+            .take_while(|&position| self.in_bounds(position))
+    }
+
     pub fn at_mut(&mut self, position: Position) -> Option<&mut C> {
         if !self.in_bounds(position) {
             None
@@ -248,3 +436,227 @@ impl<C: Cell> CellMap<C> {
         }
     }
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    Four,
+    #[allow(dead_code)]
+    Eight,
+}
+
+impl Connectivity {
+    pub fn neighbors(self, position: Position) -> Vec<Position> {
+        match self {
+            Self::Four => position.four_neighbors().to_vec(),
+            Self::Eight => position.eight_neighbors().to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Region {
+    #[allow(dead_code)]
+    pub label: usize,
+    pub cells: Vec<Position>,
+    pub perimeter: usize,
+    pub sides: usize,
+    #[allow(dead_code)]
+    pub boundary: Vec<Position>,
+}
+
+impl Region {
+    pub fn area(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+impl<C: Copy> CellMap<C> {
+    // Returns a map from each position to the label of its region, along with
+    // the regions themselves (indexed by label). Two adjacent cells are in the
+    // same region if `same_region` returns true for them.
+    pub fn regions<F>(
+        &self,
+        connectivity: Connectivity,
+        mut same_region: F,
+    ) -> (CellMap<usize>, Vec<Region>)
+    where
+        F: FnMut(C, C) -> bool,
+    {
+        let mut labels = CellMap::<Option<usize>>::filled_with(
+            None,
+            self.width as usize,
+            self.height as usize,
+        );
+        let mut cells_by_label = Vec::<Vec<Position>>::new();
+
+        for start in self.all_positions() {
+            if labels.at(start).unwrap().is_some() {
+                continue;
+            }
+
+            let label = cells_by_label.len();
+            let mut cells = Vec::<Position>::new();
+            let mut to_visit = VecDeque::<Position>::new();
+            *labels.at_mut(start).unwrap() = Some(label);
+            to_visit.push_back(start);
+
+            while let Some(position) = to_visit.pop_front() {
+                cells.push(position);
+                let cell = self.at(position).unwrap();
+                for neighbor in connectivity.neighbors(position) {
+                    let Some(neighbor_cell) = self.at(neighbor) else {
+                        continue;
+                    };
+                    let neighbor_label = labels.at_mut(neighbor).unwrap();
+                    if neighbor_label.is_none()
+                        && same_region(cell, neighbor_cell)
+                    {
+                        *neighbor_label = Some(label);
+                        to_visit.push_back(neighbor);
+                    }
+                }
+            }
+
+            cells_by_label.push(cells);
+        }
+
+        let labels = CellMap {
+            layout: labels
+                .layout
+                .into_iter()
+                .map(|row| row.into_iter().map(Option::unwrap).collect())
+                .collect(),
+            width: self.width,
+            height: self.height,
+        };
+
+        let regions = cells_by_label
+            .into_iter()
+            .enumerate()
+            .map(|(label, cells)| labels.region_with_cells(label, cells))
+            .collect();
+
+        (labels, regions)
+    }
+
+    // Returns every position reachable from `starts` by moving between cells
+    // for which `passable` returns true. Starting positions that are out of
+    // bounds or impassable are ignored.
+    pub fn flood_fill<I, F>(
+        &self,
+        starts: I,
+        connectivity: Connectivity,
+        mut passable: F,
+    ) -> CellMap<bool>
+    where
+        I: IntoIterator<Item = Position>,
+        F: FnMut(Position, C) -> bool,
+    {
+        let mut filled = CellMap::<bool>::filled_with(
+            false,
+            self.width as usize,
+            self.height as usize,
+        );
+        let mut to_visit = VecDeque::<Position>::new();
+        let mut try_visit =
+            |position: Position, to_visit: &mut VecDeque<Position>| {
+                let Some(cell) = self.at(position) else {
+                    return;
+                };
+                let is_filled = filled.at_mut(position).unwrap();
+                if !*is_filled && passable(position, cell) {
+                    *is_filled = true;
+                    to_visit.push_back(position);
+                }
+            };
+
+        for start in starts {
+            try_visit(start, &mut to_visit);
+        }
+        while let Some(position) = to_visit.pop_front() {
+            for neighbor in connectivity.neighbors(position) {
+                try_visit(neighbor, &mut to_visit);
+            }
+        }
+
+        filled
+    }
+
+    pub fn edge_positions(&self) -> impl Iterator<Item = Position> {
+        self.all_positions().filter(|position| {
+            position.row == 0
+                || position.row == self.height - 1
+                || position.col == 0
+                || position.col == self.width - 1
+        })
+    }
+
+    // Returns the passable positions that cannot be reached from the edges of
+    // the map.
+    #[allow(dead_code)]
+    pub fn enclosed<F>(
+        &self,
+        connectivity: Connectivity,
+        mut passable: F,
+    ) -> Vec<Position>
+    where
+        F: FnMut(Position, C) -> bool,
+    {
+        let outside =
+            self.flood_fill(self.edge_positions(), connectivity, &mut passable);
+        self.all_positions()
+            .filter(|&position| {
+                !outside.at(position).unwrap()
+                    && passable(position, self.at(position).unwrap())
+            })
+            .collect()
+    }
+}
+
+impl CellMap<usize> {
+    fn region_with_cells(&self, label: usize, cells: Vec<Position>) -> Region {
+        let in_region = |position: Position| self.at(position) == Some(label);
+
+        let mut perimeter = 0usize;
+        let mut sides = 0usize;
+        let mut boundary = Vec::<Position>::new();
+        for &position in cells.iter() {
+            let outside_edges = Direction::ALL
+                .into_iter()
+                .filter(|&direction| !in_region(position.move_one(direction)))
+                .count();
+            perimeter += outside_edges;
+            if outside_edges > 0 {
+                boundary.push(position);
+            }
+
+            // A region has as many sides as it has corners. Each corner of a
+            // cell is either convex (both orthogonal neighbors outside) or
+            // concave (both inside, but the diagonal outside).
+            for (vertical, horizontal) in [
+                (Direction::Up, Direction::Left),
+                (Direction::Up, Direction::Right),
+                (Direction::Down, Direction::Left),
+                (Direction::Down, Direction::Right),
+            ] {
+                let vertical_in = in_region(position.move_one(vertical));
+                let horizontal_in = in_region(position.move_one(horizontal));
+                let diagonal_in =
+                    in_region(position.move_one(vertical).move_one(horizontal));
+                if (!vertical_in && !horizontal_in)
+                    || (vertical_in && horizontal_in && !diagonal_in)
+                {
+                    sides += 1;
+                }
+            }
+        }
+
+        Region {
+            label,
+            cells,
+            perimeter,
+            sides,
+            boundary,
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::ops::Range;
+
+use crate::cellmap::{CellMap, Position};
+
+// A grid over a huge coordinate space where only a few rows and columns are
+// interesting. Each compressed cell covers a rectangle of real cells, with
+// rows (and columns) split at the given breakpoints. One extra row and column
+// of padding is added on each side so that the outside can be flood filled.
+pub struct CompressedGrid<C> {
+    row_bounds: Vec<isize>,
+    col_bounds: Vec<isize>,
+    cells: CellMap<C>,
+}
+
+fn bounds_from_breakpoints<I>(breakpoints: I) -> Vec<isize>
+where
+    I: IntoIterator<Item = isize>,
+{
+    let mut bounds = breakpoints.into_iter().collect::<Vec<_>>();
+    bounds.sort();
+    bounds.dedup();
+    let first = bounds.first().copied().unwrap_or(0);
+    let last = bounds.last().copied().unwrap_or(0);
+    bounds.insert(0, first - 1);
+    bounds.push(last + 1);
+    bounds
+}
+
+fn index_containing(bounds: &[isize], value: isize) -> Option<usize> {
+    match bounds.binary_search(&value) {
+        Ok(index) if index == bounds.len() - 1 => None,
+        Ok(index) => Some(index),
+        Err(0) => None,
+        Err(index) if index == bounds.len() => None,
+        Err(index) => Some(index - 1),
+    }
+}
+
+impl<C: Copy> CompressedGrid<C> {
+    // A compressed row starts at each row breakpoint, so a rectangle covering
+    // rows top..=bottom needs breakpoints at both top and bottom + 1 to be
+    // represented exactly (and similarly for columns).
+    pub fn new<R, S>(row_breakpoints: R, col_breakpoints: S, cell: C) -> Self
+    where
+        R: IntoIterator<Item = isize>,
+        S: IntoIterator<Item = isize>,
+    {
+        let row_bounds = bounds_from_breakpoints(row_breakpoints);
+        let col_bounds = bounds_from_breakpoints(col_breakpoints);
+        let cells = CellMap::filled_with(
+            cell,
+            col_bounds.len() - 1,
+            row_bounds.len() - 1,
+        );
+        Self {
+            row_bounds,
+            col_bounds,
+            cells,
+        }
+    }
+
+    pub fn cells(&self) -> &CellMap<C> {
+        &self.cells
+    }
+
+    #[allow(dead_code)]
+    pub fn cells_mut(&mut self) -> &mut CellMap<C> {
+        &mut self.cells
+    }
+
+    // Returns the compressed cell containing a real position
+    #[allow(dead_code)]
+    pub fn compress(&self, position: Position) -> Option<Position> {
+        Some(Position {
+            row: index_containing(&self.row_bounds, position.row)? as isize,
+            col: index_containing(&self.col_bounds, position.col)? as isize,
+        })
+    }
+
+    // Returns the real rows covered by a compressed row
+    pub fn real_rows(&self, compressed_row: isize) -> Range<isize> {
+        let row = compressed_row as usize;
+        self.row_bounds[row]..self.row_bounds[row + 1]
+    }
+
+    // Returns the real columns covered by a compressed column
+    pub fn real_cols(&self, compressed_col: isize) -> Range<isize> {
+        let col = compressed_col as usize;
+        self.col_bounds[col]..self.col_bounds[col + 1]
+    }
+
+    // The number of real cells covered by a compressed cell
+    pub fn real_area(&self, compressed: Position) -> i64 {
+        (self.real_rows(compressed.row).len()
+            * self.real_cols(compressed.col).len()) as i64
+    }
+
+    // Returns the compressed rows and columns exactly covering the real
+    // rectangle with the given (inclusive) corners, if the rectangle lines up
+    // with the breakpoints.
+    pub fn compress_rect(
+        &self,
+        corner_1: Position,
+        corner_2: Position,
+    ) -> Option<(Range<usize>, Range<usize>)> {
+        let top = corner_1.row.min(corner_2.row);
+        let bottom = corner_1.row.max(corner_2.row);
+        let left = corner_1.col.min(corner_2.col);
+        let right = corner_1.col.max(corner_2.col);
+
+        let find = |bounds: &[isize], value| bounds.binary_search(&value).ok();
+        let rows =
+            find(&self.row_bounds, top)?..find(&self.row_bounds, bottom + 1)?;
+        let cols =
+            find(&self.col_bounds, left)?..find(&self.col_bounds, right + 1)?;
+        Some((rows, cols))
+    }
+
+    // Sets every compressed cell in the real rectangle to `cell`. Returns false
+    // (without changing anything) if the rectangle isn't representable.
+    pub fn fill_rect(
+        &mut self,
+        corner_1: Position,
+        corner_2: Position,
+        cell: C,
+    ) -> bool {
+        let Some((rows, cols)) = self.compress_rect(corner_1, corner_2) else {
+            return false;
+        };
+        for row in rows {
+            for col in cols.clone() {
+                let position = Position {
+                    row: row as isize,
+                    col: col as isize,
+                };
+                *self.cells.at_mut(position).unwrap() = cell;
+            }
+        }
+        true
+    }
+
+    // Precomputes the real area of the compressed cells matching `predicate`,
+    // so that the matching area within any representable rectangle can be
+    // found in constant time.
+    pub fn area_sums<F>(&self, mut predicate: F) -> AreaSums
+    where
+        F: FnMut(Position, C) -> bool,
+    {
+        let height = self.cells.height() as usize;
+        let width = self.cells.width() as usize;
+        let mut sums = vec![vec![0i64; width + 1]; height + 1];
+        for row in 0..height {
+            for col in 0..width {
+                let position = Position {
+                    row: row as isize,
+                    col: col as isize,
+                };
+                let area =
+                    if predicate(position, self.cells.at(position).unwrap()) {
+                        self.real_area(position)
+                    } else {
+                        0
+                    };
+                sums[row + 1][col + 1] =
+                    area + sums[row][col + 1] + sums[row + 1][col]
+                        - sums[row][col];
+            }
+        }
+        AreaSums { sums }
+    }
+}
+
+pub struct AreaSums {
+    sums: Vec<Vec<i64>>,
+}
+
+impl AreaSums {
+    pub fn sum(&self, rows: Range<usize>, cols: Range<usize>) -> i64 {
+        self.sums[rows.end][cols.end]
+            - self.sums[rows.start][cols.end]
+            - self.sums[rows.end][cols.start]
+            + self.sums[rows.start][cols.start]
+    }
+}
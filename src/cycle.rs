@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Cycle {
+    // The number of steps before the state first enters the cycle
+    pub start: usize,
+    pub length: usize,
+}
+
+// Steps `state` forward until its fingerprint matches an earlier one, or until
+// `max_steps` steps have been taken. Calls `on_step` before every step, and
+// once more at the end. Returns the cycle (if one was found) and the number of
+// steps taken.
+fn step_until_cycle<S, K, F, G, H>(
+    state: &mut S,
+    max_steps: usize,
+    mut step: F,
+    mut fingerprint: G,
+    mut on_step: H,
+) -> (Option<Cycle>, usize)
+where
+    K: Hash + Eq,
+    F: FnMut(&mut S),
+    G: FnMut(&S) -> K,
+    H: FnMut(&S),
+{
+    let mut seen = HashMap::<K, usize>::new();
+    for steps_taken in 0..max_steps {
+        on_step(state);
+        let key = fingerprint(state);
+        if let Some(&start) = seen.get(&key) {
+            let cycle = Cycle {
+                start,
+                length: steps_taken - start,
+            };
+            return (Some(cycle), steps_taken);
+        }
+        seen.insert(key, steps_taken);
+        step(state);
+    }
+    on_step(state);
+    (None, max_steps)
+}
+
+// Steps `state` forward until its fingerprint matches an earlier one, calling
+// `on_step` before every step. There must eventually be a repeat.
+pub fn find_cycle<S, K, F, G, H>(
+    state: &mut S,
+    step: F,
+    fingerprint: G,
+    on_step: H,
+) -> Cycle
+where
+    K: Hash + Eq,
+    F: FnMut(&mut S),
+    G: FnMut(&S) -> K,
+    H: FnMut(&S),
+{
+    step_until_cycle(state, usize::MAX, step, fingerprint, on_step)
+        .0
+        .unwrap()
+}
+
+// Advances `state` by `num_steps` steps, skipping over whole cycles once the
+// fingerprints of the states start repeating
+pub fn advance<S, K, F, G>(
+    state: &mut S,
+    num_steps: usize,
+    mut step: F,
+    fingerprint: G,
+) -> Option<Cycle>
+where
+    K: Hash + Eq,
+    F: FnMut(&mut S),
+    G: FnMut(&S) -> K,
+{
+    let (cycle, steps_taken) =
+        step_until_cycle(state, num_steps, &mut step, fingerprint, |_| {});
+    if let Some(cycle) = cycle {
+        for _ in 0..(num_steps - steps_taken) % cycle.length {
+            step(state);
+        }
+    }
+    cycle
+}
+
+// Returns `metric` of the state after `num_steps` steps. Once the fingerprints
+// start repeating, the metric is assumed to change by the same amount over
+// every cycle (e.g. a tower's height), so huge step counts can be
+// extrapolated.
+pub fn metric_after<S, K, F, G, M>(
+    state: &mut S,
+    num_steps: usize,
+    step: F,
+    fingerprint: G,
+    mut metric: M,
+) -> i64
+where
+    K: Hash + Eq,
+    F: FnMut(&mut S),
+    G: FnMut(&S) -> K,
+    M: FnMut(&S) -> i64,
+{
+    let mut metrics = Vec::<i64>::new();
+    let (cycle, _) =
+        step_until_cycle(state, num_steps, step, fingerprint, |state| {
+            metrics.push(metric(state))
+        });
+    let Some(cycle) = cycle else {
+        return *metrics.last().unwrap();
+    };
+
+    let cycles = ((num_steps - cycle.start) / cycle.length) as i64;
+    let offset = (num_steps - cycle.start) % cycle.length;
+    let change_per_cycle =
+        metrics[cycle.start + cycle.length] - metrics[cycle.start];
+    metrics[cycle.start + offset] + cycles * change_per_cycle
+}
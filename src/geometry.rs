@@ -0,0 +1,213 @@
+use std::cmp::{max, min};
+use std::io;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use crate::cellmap::{Direction, Direction8, Position};
+use crate::errors::invalid_input;
+use crate::math::gcd;
+use crate::parse::parse_all;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point3D {
+    pub x: i64,
+    pub y: i64,
+    pub z: i64,
+}
+
+impl Point3D {
+    #[allow(dead_code)]
+    pub fn manhattan_distance(self, other: Self) -> i64 {
+        (self.x - other.x).abs()
+            + (self.y - other.y).abs()
+            + (self.z - other.z).abs()
+    }
+
+    #[allow(dead_code)]
+    pub fn chebyshev_distance(self, other: Self) -> i64 {
+        max(
+            max((self.x - other.x).abs(), (self.y - other.y).abs()),
+            (self.z - other.z).abs(),
+        )
+    }
+
+    pub fn squared_distance(self, other: Self) -> i64 {
+        (self.x - other.x).pow(2)
+            + (self.y - other.y).pow(2)
+            + (self.z - other.z).pow(2)
+    }
+}
+
+impl Add for Point3D {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+        }
+    }
+}
+
+impl AddAssign for Point3D {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Point3D {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            x: self.x - other.x,
+            y: self.y - other.y,
+            z: self.z - other.z,
+        }
+    }
+}
+
+impl SubAssign for Point3D {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl Mul<i64> for Point3D {
+    type Output = Self;
+
+    fn mul(self, scalar: i64) -> Self {
+        Self {
+            x: self.x * scalar,
+            y: self.y * scalar,
+            z: self.z * scalar,
+        }
+    }
+}
+
+impl Neg for Point3D {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+        }
+    }
+}
+
+impl FromStr for Point3D {
+    type Err = io::Error;
+
+    fn from_str(string: &str) -> io::Result<Self> {
+        let &[x, y, z] =
+            &parse_all::<_, i64>(string.split(',').map(str::trim))?[..]
+        else {
+            return Err(invalid_input("Expected x,y,z"));
+        };
+        Ok(Self { x, y, z })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PointLocation {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+fn edges(vertices: &[Position]) -> impl Iterator<Item = (Position, Position)> {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(&start, &end)| (start, end))
+}
+
+// Returns the vertices visited by starting at `start` and making each move in
+// turn. The final move is expected to return to `start`, which is not
+// repeated.
+pub fn vertices_from_moves<I>(start: Position, moves: I) -> Vec<Position>
+where
+    I: IntoIterator<Item = (Direction, i64)>,
+{
+    let mut vertices = vec![start];
+    for (direction, length) in moves {
+        let last = *vertices.last().unwrap();
+        vertices
+            .push(Direction8::from(direction).move_n(last, length as isize));
+    }
+    if vertices.len() > 1 && vertices.last() == Some(&start) {
+        vertices.pop();
+    }
+    vertices
+}
+
+// Twice the signed area of the polygon, via the shoelace formula. This is
+// positive if the vertices go clockwise on screen (i.e. with rows increasing
+// downwards).
+pub fn double_signed_area(vertices: &[Position]) -> i128 {
+    edges(vertices)
+        .map(|(start, end)| {
+            start.col as i128 * end.row as i128
+                - end.col as i128 * start.row as i128
+        })
+        .sum()
+}
+
+// Twice the area, so that polygons with half-integer areas stay exact
+pub fn double_area(vertices: &[Position]) -> i128 {
+    double_signed_area(vertices).abs()
+}
+
+// The number of lattice points on the polygon's edges
+pub fn boundary_points(vertices: &[Position]) -> i128 {
+    edges(vertices)
+        .map(|(start, end)| {
+            gcd((end.row - start.row) as i128, (end.col - start.col) as i128)
+        })
+        .sum()
+}
+
+// The number of lattice points strictly inside the polygon, by Pick's theorem:
+// A = I + B/2 - 1
+pub fn interior_points(vertices: &[Position]) -> i128 {
+    (double_area(vertices) - boundary_points(vertices) + 2) / 2
+}
+
+// The number of lattice points inside or on the polygon. For a polygon traced
+// through the centers of grid cells, this is the number of cells it covers.
+pub fn enclosed_points(vertices: &[Position]) -> i128 {
+    interior_points(vertices) + boundary_points(vertices)
+}
+
+#[allow(dead_code)]
+pub fn locate_point(vertices: &[Position], point: Position) -> PointLocation {
+    let mut inside = false;
+    for (start, end) in edges(vertices) {
+        let cross = (end.col - start.col) as i128
+            * (point.row - start.row) as i128
+            - (point.col - start.col) as i128 * (end.row - start.row) as i128;
+        let in_bounding_box = point.row >= min(start.row, end.row)
+            && point.row <= max(start.row, end.row)
+            && point.col >= min(start.col, end.col)
+            && point.col <= max(start.col, end.col);
+        if cross == 0 && in_bounding_box {
+            return PointLocation::Boundary;
+        }
+
+        // Cast a ray to the right of the point and count edge crossings
+        if (start.row > point.row) != (end.row > point.row)
+            && (cross > 0) == (end.row > start.row)
+        {
+            inside = !inside;
+        }
+    }
+
+    if inside {
+        PointLocation::Inside
+    } else {
+        PointLocation::Outside
+    }
+}
@@ -0,0 +1,527 @@
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::io;
+
+use crate::errors::invalid_input;
+
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    ids: HashMap<String, usize>,
+    names: Vec<String>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_owned());
+        self.ids.insert(name.to_owned(), id);
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+// A graph whose vertices are identified by name, and stored by the index
+// assigned to that name. Undirected graphs store each edge in both directions.
+#[derive(Clone, Debug)]
+pub struct Graph {
+    names: Interner,
+    adjacency: Vec<Vec<usize>>,
+    is_directed: bool,
+}
+
+impl Graph {
+    pub fn new_directed() -> Self {
+        Self {
+            names: Interner::new(),
+            adjacency: Vec::new(),
+            is_directed: true,
+        }
+    }
+
+    pub fn new_undirected() -> Self {
+        Self {
+            names: Interner::new(),
+            adjacency: Vec::new(),
+            is_directed: false,
+        }
+    }
+
+    // Parses lines of the form "a: b c d", meaning that a has edges to b, c
+    // and d
+    pub fn from_adjacency_lines<I>(
+        lines: I,
+        is_directed: bool,
+    ) -> io::Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut graph = if is_directed {
+            Self::new_directed()
+        } else {
+            Self::new_undirected()
+        };
+        for line in lines {
+            let Some((name, neighbor_names)) = line.as_ref().split_once(": ")
+            else {
+                return Err(invalid_input("Expected <vertex>: <neighbors>"));
+            };
+            let vertex = graph.add_vertex(name);
+            for neighbor_name in neighbor_names.split_whitespace() {
+                let neighbor = graph.add_vertex(neighbor_name);
+                graph.add_edge(vertex, neighbor);
+            }
+        }
+        Ok(graph)
+    }
+
+    // Parses lines of the form "a-b", meaning that a and b are connected
+    pub fn from_edge_lines<I>(lines: I, is_directed: bool) -> io::Result<Self>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut graph = if is_directed {
+            Self::new_directed()
+        } else {
+            Self::new_undirected()
+        };
+        for line in lines {
+            let Some((name_1, name_2)) = line.as_ref().split_once('-') else {
+                return Err(invalid_input("Expected <vertex>-<vertex>"));
+            };
+            let vertex_1 = graph.add_vertex(name_1);
+            let vertex_2 = graph.add_vertex(name_2);
+            graph.add_edge(vertex_1, vertex_2);
+        }
+        Ok(graph)
+    }
+
+    pub fn add_vertex(&mut self, name: &str) -> usize {
+        let id = self.names.intern(name);
+        if id == self.adjacency.len() {
+            self.adjacency.push(Vec::new());
+        }
+        id
+    }
+
+    // Adding an edge that already exists has no effect
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        if !self.adjacency[from].contains(&to) {
+            self.adjacency[from].push(to);
+        }
+        if !self.is_directed && !self.adjacency[to].contains(&from) {
+            self.adjacency[to].push(from);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn remove_edge(&mut self, from: usize, to: usize) {
+        self.adjacency[from].retain(|&vertex| vertex != to);
+        if !self.is_directed {
+            self.adjacency[to].retain(|&vertex| vertex != from);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.adjacency[from].contains(&to)
+    }
+
+    pub fn num_vertices(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.names.id(name)
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        self.names.name(id)
+    }
+
+    pub fn neighbors(&self, vertex: usize) -> &[usize] {
+        &self.adjacency[vertex]
+    }
+
+    // Returns a graph with every edge reversed. Vertices keep their IDs.
+    pub fn reversed(&self) -> Self {
+        let mut adjacency = vec![Vec::new(); self.num_vertices()];
+        for (from, neighbors) in self.adjacency.iter().enumerate() {
+            for &to in neighbors {
+                adjacency[to].push(from);
+            }
+        }
+        Self {
+            names: self.names.clone(),
+            adjacency,
+            is_directed: self.is_directed,
+        }
+    }
+
+    // Returns the number of edges on the shortest path from `start` to each
+    // vertex, if there is one
+    pub fn bfs_distances(&self, start: usize) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.num_vertices()];
+        let mut to_visit = VecDeque::<usize>::new();
+        distances[start] = Some(0);
+        to_visit.push_back(start);
+        while let Some(vertex) = to_visit.pop_front() {
+            let distance = distances[vertex].unwrap();
+            for &neighbor in self.neighbors(vertex) {
+                if distances[neighbor].is_none() {
+                    distances[neighbor] = Some(distance + 1);
+                    to_visit.push_back(neighbor);
+                }
+            }
+        }
+        distances
+    }
+
+    pub fn all_pairs_shortest_paths(&self) -> Vec<Vec<Option<usize>>> {
+        (0..self.num_vertices())
+            .map(|start| self.bfs_distances(start))
+            .collect()
+    }
+
+    // Returns the vertices reachable from `start`, in depth-first preorder
+    #[allow(dead_code)]
+    pub fn dfs_order(&self, start: usize) -> Vec<usize> {
+        let mut visited = vec![false; self.num_vertices()];
+        let mut order = Vec::<usize>::new();
+        let mut to_visit = vec![start];
+        while let Some(vertex) = to_visit.pop() {
+            if visited[vertex] {
+                continue;
+            }
+            visited[vertex] = true;
+            order.push(vertex);
+            to_visit.extend(self.neighbors(vertex).iter().rev());
+        }
+        order
+    }
+
+    // Returns None if the graph has a cycle
+    #[allow(dead_code)]
+    pub fn topological_sort(&self) -> Option<Vec<usize>> {
+        let mut in_degrees = vec![0usize; self.num_vertices()];
+        for neighbors in self.adjacency.iter() {
+            for &neighbor in neighbors {
+                in_degrees[neighbor] += 1;
+            }
+        }
+
+        let mut ready = (0..self.num_vertices())
+            .filter(|&vertex| in_degrees[vertex] == 0)
+            .collect::<VecDeque<_>>();
+        let mut order = Vec::<usize>::with_capacity(self.num_vertices());
+        while let Some(vertex) = ready.pop_front() {
+            order.push(vertex);
+            for &neighbor in self.neighbors(vertex) {
+                in_degrees[neighbor] -= 1;
+                if in_degrees[neighbor] == 0 {
+                    ready.push_back(neighbor);
+                }
+            }
+        }
+
+        if order.len() == self.num_vertices() {
+            Some(order)
+        } else {
+            None
+        }
+    }
+
+    // Uses Tarjan's algorithm, without recursion. For undirected graphs, these
+    // are the connected components.
+    #[allow(dead_code)]
+    pub fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        let num_vertices = self.num_vertices();
+        let mut indices = vec![None; num_vertices];
+        let mut low_links = vec![0usize; num_vertices];
+        let mut on_stack = vec![false; num_vertices];
+        let mut stack = Vec::<usize>::new();
+        let mut components = Vec::<Vec<usize>>::new();
+        let mut next_index = 0usize;
+
+        for root in 0..num_vertices {
+            if indices[root].is_some() {
+                continue;
+            }
+
+            // Each entry is a vertex and the number of its neighbors visited
+            let mut call_stack = vec![(root, 0usize)];
+            while let Some(&mut (vertex, ref mut neighbor_index)) =
+                call_stack.last_mut()
+            {
+                if *neighbor_index == 0 && indices[vertex].is_none() {
+                    indices[vertex] = Some(next_index);
+                    low_links[vertex] = next_index;
+                    next_index += 1;
+                    stack.push(vertex);
+                    on_stack[vertex] = true;
+                }
+
+                if let Some(&neighbor) =
+                    self.neighbors(vertex).get(*neighbor_index)
+                {
+                    *neighbor_index += 1;
+                    match indices[neighbor] {
+                        None => call_stack.push((neighbor, 0)),
+                        Some(index) if on_stack[neighbor] => {
+                            low_links[vertex] = low_links[vertex].min(index);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    low_links[parent] =
+                        low_links[parent].min(low_links[vertex]);
+                }
+
+                if Some(low_links[vertex]) == indices[vertex] {
+                    let mut component = Vec::<usize>::new();
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == vertex {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+
+        components
+    }
+
+    // Uses the Stoer-Wagner algorithm, treating the graph as undirected with
+    // every edge having weight 1. Returns the number of edges in a minimum cut
+    // and the vertices on one side of it, or None if there are fewer than two
+    // vertices.
+    pub fn minimum_cut(&self) -> Option<(usize, Vec<usize>)> {
+        let num_vertices = self.num_vertices();
+        if num_vertices < 2 {
+            return None;
+        }
+
+        let mut weights = vec![HashMap::<usize, usize>::new(); num_vertices];
+        for (from, neighbors) in self.adjacency.iter().enumerate() {
+            for &to in neighbors {
+                if from != to {
+                    weights[from].insert(to, 1);
+                    weights[to].insert(from, 1);
+                }
+            }
+        }
+        let mut members = (0..num_vertices)
+            .map(|vertex| vec![vertex])
+            .collect::<Vec<_>>();
+        let mut is_merged = vec![false; num_vertices];
+        let mut best: Option<(usize, Vec<usize>)> = None;
+
+        for phase in 0..num_vertices - 1 {
+            // Repeatedly add the vertex most tightly connected to those
+            // already added. The last vertex added is separated from
+            // everything else by a minimum cut between it and the second last.
+            let mut is_added = vec![false; num_vertices];
+            let mut connectivity = vec![0usize; num_vertices];
+            let mut heap = (0..num_vertices)
+                .filter(|&vertex| !is_merged[vertex])
+                .map(|vertex| (0usize, vertex))
+                .collect::<BinaryHeap<_>>();
+            let mut second_last = None;
+            let mut last = None;
+            let mut cut_weight = 0usize;
+            for _ in 0..num_vertices - phase {
+                let vertex = loop {
+                    let (weight, vertex) = heap.pop().unwrap();
+                    if !is_added[vertex] && weight == connectivity[vertex] {
+                        break vertex;
+                    }
+                };
+                is_added[vertex] = true;
+                second_last = last;
+                last = Some(vertex);
+                cut_weight = connectivity[vertex];
+                for (&neighbor, &weight) in weights[vertex].iter() {
+                    if !is_added[neighbor] {
+                        connectivity[neighbor] += weight;
+                        heap.push((connectivity[neighbor], neighbor));
+                    }
+                }
+            }
+            let (second_last, last) = (second_last.unwrap(), last.unwrap());
+
+            if best.as_ref().is_none_or(|(weight, _)| cut_weight < *weight) {
+                best = Some((cut_weight, members[last].clone()));
+            }
+
+            // Merge the last vertex into the second last
+            let merged_members = std::mem::take(&mut members[last]);
+            members[second_last].extend(merged_members);
+            for (neighbor, weight) in std::mem::take(&mut weights[last]) {
+                weights[neighbor].remove(&last);
+                if neighbor != second_last {
+                    *weights[second_last].entry(neighbor).or_default() +=
+                        weight;
+                    *weights[neighbor].entry(second_last).or_default() +=
+                        weight;
+                }
+            }
+            is_merged[last] = true;
+        }
+
+        best
+    }
+
+    fn neighbor_sets(&self) -> Vec<HashSet<usize>> {
+        self.adjacency
+            .iter()
+            .enumerate()
+            .map(|(vertex, neighbors)| {
+                neighbors
+                    .iter()
+                    .copied()
+                    .filter(|&neighbor| neighbor != vertex)
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Returns every clique of exactly `size` vertices in an undirected graph,
+    // each sorted by vertex ID
+    pub fn cliques_of_size(&self, size: usize) -> Vec<Vec<usize>> {
+        fn extend(
+            neighbor_sets: &[HashSet<usize>],
+            clique: &mut Vec<usize>,
+            candidates: &[usize],
+            size: usize,
+            cliques: &mut Vec<Vec<usize>>,
+        ) {
+            if clique.len() == size {
+                cliques.push(clique.clone());
+                return;
+            }
+            for (i, &vertex) in candidates.iter().enumerate() {
+                let new_candidates = candidates[i + 1..]
+                    .iter()
+                    .copied()
+                    .filter(|other| neighbor_sets[vertex].contains(other))
+                    .collect::<Vec<_>>();
+                clique.push(vertex);
+                extend(neighbor_sets, clique, &new_candidates, size, cliques);
+                clique.pop();
+            }
+        }
+
+        let neighbor_sets = self.neighbor_sets();
+        let all_vertices = (0..self.num_vertices()).collect::<Vec<_>>();
+        let mut cliques = Vec::<Vec<usize>>::new();
+        if size > 0 {
+            extend(
+                &neighbor_sets,
+                &mut Vec::new(),
+                &all_vertices,
+                size,
+                &mut cliques,
+            );
+        }
+        cliques
+    }
+
+    // Uses the Bron-Kerbosch algorithm with pivoting to find every clique in
+    // an undirected graph that can't be extended, each sorted by vertex ID
+    pub fn maximal_cliques(&self) -> Vec<Vec<usize>> {
+        fn bron_kerbosch(
+            neighbor_sets: &[HashSet<usize>],
+            clique: &mut Vec<usize>,
+            mut candidates: HashSet<usize>,
+            mut excluded: HashSet<usize>,
+            cliques: &mut Vec<Vec<usize>>,
+        ) {
+            if candidates.is_empty() && excluded.is_empty() {
+                let mut sorted_clique = clique.clone();
+                sorted_clique.sort();
+                cliques.push(sorted_clique);
+                return;
+            }
+
+            // Any maximal clique must include the pivot or one of its
+            // non-neighbors, so only those need to be tried here.
+            let pivot = *candidates
+                .union(&excluded)
+                .max_by_key(|&&vertex| {
+                    candidates.intersection(&neighbor_sets[vertex]).count()
+                })
+                .unwrap();
+            let to_try = candidates
+                .difference(&neighbor_sets[pivot])
+                .copied()
+                .collect::<Vec<_>>();
+
+            for vertex in to_try {
+                clique.push(vertex);
+                bron_kerbosch(
+                    neighbor_sets,
+                    clique,
+                    &candidates & &neighbor_sets[vertex],
+                    &excluded & &neighbor_sets[vertex],
+                    cliques,
+                );
+                clique.pop();
+                candidates.remove(&vertex);
+                excluded.insert(vertex);
+            }
+        }
+
+        let neighbor_sets = self.neighbor_sets();
+        let mut cliques = Vec::<Vec<usize>>::new();
+        bron_kerbosch(
+            &neighbor_sets,
+            &mut Vec::new(),
+            (0..self.num_vertices()).collect(),
+            HashSet::new(),
+            &mut cliques,
+        );
+        cliques
+    }
+
+    // Returns all of the largest cliques in an undirected graph, since there
+    // may be ties
+    pub fn maximum_cliques(&self) -> Vec<Vec<usize>> {
+        let cliques = self.maximal_cliques();
+        let max_size = cliques.iter().map(Vec::len).max().unwrap_or(0);
+        cliques
+            .into_iter()
+            .filter(|clique| clique.len() == max_size)
+            .collect()
+    }
+}
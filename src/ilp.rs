@@ -0,0 +1,262 @@
+use num_rational::Ratio;
+
+use crate::math::lcm;
+use crate::matrix::{Matrix, Solution};
+
+// Upper bounds on each variable of coefficients * x = target when every
+// coefficient, target and variable is non-negative (as when counting button
+// presses): a variable can't be larger than any target it contributes to
+// allows. Variables that contribute to nothing are bounded by 0, since using
+// them never helps.
+pub fn press_upper_bounds(
+    coefficients: &Matrix<i64>,
+    target: &[i64],
+) -> Vec<i64> {
+    (0..coefficients.num_cols())
+        .map(|col| {
+            (0..coefficients.num_rows())
+                .filter(|&row| *coefficients[(row, col)].numer() > 0)
+                .map(|row| {
+                    (Ratio::from_integer(target[row])
+                        / coefficients[(row, col)])
+                        .floor()
+                        .to_integer()
+                })
+                .min()
+                .unwrap_or(0)
+        })
+        .collect()
+}
+
+// The range of t such that 0 <= base + t * direction <= upper, all scaled up
+// by `denominator`
+fn parameter_range(
+    base: i64,
+    direction: i64,
+    upper: i64,
+    denominator: i64,
+) -> (i64, i64) {
+    let (low, high) = (-base, upper * denominator - base);
+    if direction > 0 {
+        (-((-low).div_euclid(direction)), high.div_euclid(direction))
+    } else {
+        (
+            -(high.div_euclid(-direction)),
+            (-low).div_euclid(-direction),
+        )
+    }
+}
+
+struct FreeVariable {
+    // How the (scaled) variables change as this free variable goes up by 1
+    direction: Vec<i64>,
+    // How the (scaled) sum changes as this free variable goes up by 1
+    cost: i64,
+    low: i64,
+    high: i64,
+}
+
+impl FreeVariable {
+    fn new(direction: Vec<i64>, (low, high): (i64, i64)) -> Self {
+        Self {
+            cost: direction.iter().sum(),
+            direction,
+            low,
+            high,
+        }
+    }
+
+    // The value in range that keeps the sum smallest
+    fn cheapest(&self) -> i64 {
+        if self.cost >= 0 { self.low } else { self.high }
+    }
+
+    fn apply(&self, values: &mut [i64], times: i64) {
+        for (value, &delta) in values.iter_mut().zip(&self.direction) {
+            *value += times * delta;
+        }
+    }
+}
+
+struct Search {
+    denominator: i64,
+    free_variables: Vec<FreeVariable>,
+    upper_bounds: Vec<i64>,
+    // The variables which are fully determined once the free variables before
+    // each depth are set
+    determined_at: Vec<Vec<usize>>,
+    // The least and greatest amount the free variables from each depth
+    // onwards can change each (scaled) variable by
+    remaining_change: Vec<Vec<(i64, i64)>>,
+    // The least amount the free variables from each depth onwards can change
+    // the (scaled) sum by
+    min_remaining_cost: Vec<i64>,
+}
+
+impl Search {
+    // `values` holds every variable scaled up by the denominator, with the
+    // free variables before `depth` already applied, and `sum` is their total
+    fn run(
+        &self,
+        depth: usize,
+        values: &mut [i64],
+        sum: i64,
+        best: &mut Option<i64>,
+    ) {
+        let is_reachable = values
+            .iter()
+            .zip(&self.upper_bounds)
+            .zip(&self.remaining_change[depth])
+            .all(|((&value, &upper), &(least, greatest))| {
+                value + greatest >= 0
+                    && value + least <= upper * self.denominator
+            });
+        let is_valid = is_reachable
+            && self.determined_at[depth]
+                .iter()
+                .all(|&var| values[var] % self.denominator == 0);
+        if !is_valid {
+            return;
+        }
+        if depth == self.free_variables.len() {
+            let sum = sum / self.denominator;
+            if best.is_none_or(|best| sum < best) {
+                *best = Some(sum);
+            }
+            return;
+        }
+
+        // Only try values that keep the variables this one determines in range
+        let free_variable = &self.free_variables[depth];
+        let (mut low, mut high) = (free_variable.low, free_variable.high);
+        for &var in &self.determined_at[depth + 1] {
+            let (var_low, var_high) = parameter_range(
+                values[var],
+                free_variable.direction[var],
+                self.upper_bounds[var],
+                self.denominator,
+            );
+            (low, high) = (low.max(var_low), high.min(var_high));
+        }
+        if low > high {
+            return;
+        }
+
+        // Try values in order of increasing sum, so once the sum can't beat
+        // the best there's no point continuing
+        let (start, step) = if free_variable.cost >= 0 {
+            (low, 1)
+        } else {
+            (high, -1)
+        };
+        free_variable.apply(values, start);
+        let mut sum = sum + start * free_variable.cost;
+        let mut taken = 0;
+        while taken <= high - low {
+            let lower_bound = sum + self.min_remaining_cost[depth + 1];
+            if best.is_some_and(|best| lower_bound >= best * self.denominator) {
+                break;
+            }
+            self.run(depth + 1, values, sum, best);
+            free_variable.apply(values, step);
+            sum += step * free_variable.cost;
+            taken += 1;
+        }
+        free_variable.apply(values, -(start + taken * step));
+    }
+}
+
+// Finds the smallest sum of a non-negative integer vector x such that
+// coefficients * x = target and x[i] <= upper_bounds[i] for every i. Gaussian
+// elimination leaves only the free variables to enumerate, so this is fast as
+// long as there are few of them. Returns None if there is no solution.
+pub fn minimize_sum(
+    coefficients: &Matrix<i64>,
+    target: &[i64],
+    upper_bounds: &[i64],
+) -> Option<i64> {
+    let target = target
+        .iter()
+        .map(|&value| Ratio::from_integer(value))
+        .collect::<Vec<_>>();
+    let (particular, null_space) = match coefficients.solve(&target) {
+        Solution::None => return None,
+        Solution::Unique(particular) => (particular, Vec::new()),
+        Solution::Infinite {
+            particular,
+            null_space,
+        } => (particular, null_space),
+    };
+
+    // Scale everything up to integers, so x = (base + sum of t * direction)
+    // / denominator, where each t is the value of a free variable
+    let denominator = particular
+        .iter()
+        .chain(null_space.iter().flatten())
+        .fold(1, |denominator, value| lcm(denominator, *value.denom()));
+    let scale = |value: &Ratio<i64>| (value * denominator).to_integer();
+    let base = particular.iter().map(scale).collect::<Vec<_>>();
+    let directions = null_space
+        .iter()
+        .map(|vector| vector.iter().map(scale).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    // A variable that depends on only one free variable (including the free
+    // variable itself) bounds that free variable's range
+    let mut ranges = vec![(i64::MIN, i64::MAX); directions.len()];
+    let mut determined_at = vec![Vec::new(); directions.len() + 1];
+    for var in 0..base.len() {
+        let last_dependency =
+            (0..directions.len()).rfind(|&k| directions[k][var] != 0);
+        determined_at[last_dependency.map_or(0, |k| k + 1)].push(var);
+
+        let mut dependencies =
+            (0..directions.len()).filter(|&k| directions[k][var] != 0);
+        if let (Some(k), None) = (dependencies.next(), dependencies.next()) {
+            let (low, high) = parameter_range(
+                base[var],
+                directions[k][var],
+                upper_bounds[var],
+                denominator,
+            );
+            ranges[k] = (ranges[k].0.max(low), ranges[k].1.min(high));
+        }
+    }
+
+    let free_variables = directions
+        .into_iter()
+        .zip(ranges)
+        .map(|(direction, range)| FreeVariable::new(direction, range))
+        .collect::<Vec<_>>();
+    let mut remaining_change =
+        vec![vec![(0, 0); base.len()]; free_variables.len() + 1];
+    let mut min_remaining_cost = vec![0; free_variables.len() + 1];
+    for (depth, free_variable) in free_variables.iter().enumerate().rev() {
+        remaining_change[depth] = remaining_change[depth + 1]
+            .iter()
+            .zip(&free_variable.direction)
+            .map(|(&(least, greatest), &delta)| {
+                let (change_1, change_2) =
+                    (free_variable.low * delta, free_variable.high * delta);
+                (
+                    least + change_1.min(change_2),
+                    greatest + change_1.max(change_2),
+                )
+            })
+            .collect();
+        min_remaining_cost[depth] = min_remaining_cost[depth + 1]
+            + free_variable.cheapest() * free_variable.cost;
+    }
+
+    let search = Search {
+        denominator,
+        free_variables,
+        upper_bounds: upper_bounds.to_vec(),
+        determined_at,
+        remaining_change,
+        min_remaining_cost,
+    };
+    let mut best = None;
+    search.run(0, &mut base.clone(), base.iter().sum(), &mut best);
+    best
+}
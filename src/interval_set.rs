@@ -0,0 +1,158 @@
+use std::iter::Sum;
+use std::ops::{Add, Range, Sub};
+
+// A set of values stored as sorted half-open ranges, which are non-empty and
+// neither overlap nor touch
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Ord + Copy> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self { ranges: Vec::new() }
+    }
+
+    // Sorts and merges arbitrary ranges
+    fn normalized(mut ranges: Vec<Range<T>>) -> Self {
+        ranges.retain(|range| !range.is_empty());
+        ranges.sort_by_key(|range| range.start);
+        let mut merged = Vec::<Range<T>>::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if last.end >= range.start => {
+                    last.end = last.end.max(range.end);
+                }
+                _ => merged.push(range),
+            }
+        }
+        Self { ranges: merged }
+    }
+
+    #[allow(dead_code)]
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn min(&self) -> Option<T> {
+        self.ranges.first().map(|range| range.start)
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let index = self.ranges.partition_point(|range| range.end <= *value);
+        self.ranges
+            .get(index)
+            .is_some_and(|range| range.start <= *value)
+    }
+
+    // The number of values in the set
+    pub fn total_len<L>(&self) -> L
+    where
+        T: Sub<Output = L>,
+        L: Sum,
+    {
+        self.ranges
+            .iter()
+            .map(|range| range.end - range.start)
+            .sum()
+    }
+
+    #[allow(dead_code)]
+    pub fn insert(&mut self, range: Range<T>) {
+        *self = self.union(&Self::from(range));
+    }
+
+    #[allow(dead_code)]
+    pub fn union(&self, other: &Self) -> Self {
+        Self::normalized(
+            self.ranges.iter().chain(&other.ranges).cloned().collect(),
+        )
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (first, second) = (&self.ranges[i], &other.ranges[j]);
+            let overlap =
+                first.start.max(second.start)..first.end.min(second.end);
+            if !overlap.is_empty() {
+                ranges.push(overlap);
+            }
+            if first.end < second.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self { ranges }
+    }
+
+    // The values in self but not in other
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut ranges = Vec::new();
+        let mut j = 0;
+        for range in &self.ranges {
+            let mut start = range.start;
+            while j < other.ranges.len() && other.ranges[j].end <= start {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.ranges.len() && other.ranges[k].start < range.end {
+                if start < other.ranges[k].start {
+                    ranges.push(start..other.ranges[k].start);
+                }
+                start = start.max(other.ranges[k].end);
+                k += 1;
+            }
+            if start < range.end {
+                ranges.push(start..range.end);
+            }
+        }
+        Self { ranges }
+    }
+
+    // Moves the values in each source range by that range's offset, leaving
+    // values outside every source range where they are. The source ranges
+    // shouldn't overlap.
+    pub fn shift_pieces<D>(&self, shifts: &[(Range<T>, D)]) -> Self
+    where
+        T: Add<D, Output = T>,
+        D: Copy,
+    {
+        let mut remaining = self.clone();
+        let mut shifted = Vec::new();
+        for (source, offset) in shifts {
+            let source = Self::from(source.clone());
+            for range in self.intersection(&source).ranges {
+                shifted.push((range.start + *offset)..(range.end + *offset));
+            }
+            remaining = remaining.difference(&source);
+        }
+        shifted.extend(remaining.ranges);
+        Self::normalized(shifted)
+    }
+}
+
+impl<T: Ord + Copy> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord + Copy> From<Range<T>> for IntervalSet<T> {
+    fn from(range: Range<T>) -> Self {
+        Self::normalized(vec![range])
+    }
+}
+
+impl<T: Ord + Copy> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        Self::normalized(iter.into_iter().collect())
+    }
+}
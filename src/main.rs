@@ -3,9 +3,20 @@
 #![feature(iterator_try_collect)]
 #![feature(linked_list_cursors)]
 
+mod branch_and_bound;
 mod cellmap;
+mod compressed_grid;
+mod cycle;
 mod errors;
+mod geometry;
+mod graph;
+mod ilp;
+mod interval_set;
 mod iter;
+mod math;
+mod matrix;
+mod memo;
+mod ocr;
 mod parse;
 mod part;
 mod year2022;
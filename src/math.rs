@@ -0,0 +1,96 @@
+use std::ops::Neg;
+
+use num_integer::Integer;
+
+// Always non-negative
+pub fn gcd<T: Integer>(a: T, b: T) -> T {
+    a.gcd(&b)
+}
+
+// Always non-negative, and zero if either is zero
+pub fn lcm<T: Integer>(a: T, b: T) -> T {
+    a.lcm(&b)
+}
+
+// Returns (g, x, y) such that a * x + b * y = g = gcd(a, b)
+pub fn extended_gcd<T>(a: T, b: T) -> (T, T, T)
+where
+    T: Integer + Copy + Neg<Output = T>,
+{
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (T::one(), T::zero());
+    let (mut old_y, mut y) = (T::zero(), T::one());
+    while r != T::zero() {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_x, x) = (x, old_x - quotient * x);
+        (old_y, y) = (y, old_y - quotient * y);
+    }
+    if old_r < T::zero() {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+// Returns a mod m in the range [0, m)
+pub fn modulo<T: Integer + Copy>(a: T, m: T) -> T {
+    let result = a % m;
+    if result < T::zero() {
+        result + m
+    } else {
+        result
+    }
+}
+
+#[allow(dead_code)]
+pub fn mod_inverse<T>(a: T, m: T) -> Option<T>
+where
+    T: Integer + Copy + Neg<Output = T>,
+{
+    let (g, x, _) = extended_gcd(modulo(a, m), m);
+    if g != T::one() {
+        None
+    } else {
+        Some(modulo(x, m))
+    }
+}
+
+#[allow(dead_code)]
+pub fn mod_pow(base: i64, exponent: u64, modulus: i64) -> i64 {
+    let modulus = modulus as i128;
+    let mut base = modulo(base as i128, modulus);
+    let mut exponent = exponent;
+    let mut result = 1 % modulus;
+    while exponent > 0 {
+        if exponent % 2 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent /= 2;
+    }
+    result as i64
+}
+
+// Solves the system x = residue (mod modulus) for every (residue, modulus)
+// pair, where the moduli need not be coprime. Returns (x, m) such that the
+// solutions are exactly the numbers congruent to x mod m, with 0 <= x < m, or
+// None if there are no solutions.
+pub fn chinese_remainder(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+    let mut result = (0i128, 1i128);
+    for &(residue, modulus) in congruences {
+        let (current_residue, current_modulus) = result;
+        let (g, p, _) = extended_gcd(current_modulus, modulus);
+        let difference = residue - current_residue;
+        if difference % g != 0 {
+            return None;
+        }
+        let new_modulus = current_modulus / g * modulus;
+        let step = modulo(difference / g % (modulus / g) * p, modulus / g);
+        result = (
+            modulo(current_residue + current_modulus * step, new_modulus),
+            new_modulus,
+        );
+    }
+    Some(result)
+}
@@ -0,0 +1,236 @@
+use std::ops::{Index, IndexMut};
+
+use num_integer::Integer;
+use num_rational::Ratio;
+
+// A dense matrix of exact rationals, so elimination never loses precision
+#[derive(Clone, Debug)]
+pub struct Matrix<T> {
+    num_rows: usize,
+    num_cols: usize,
+    // Row-major
+    entries: Vec<Ratio<T>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Solution<T> {
+    None,
+    Unique(Vec<Ratio<T>>),
+    // Every solution is `particular` plus some linear combination of the
+    // `null_space` basis vectors
+    Infinite {
+        particular: Vec<Ratio<T>>,
+        null_space: Vec<Vec<Ratio<T>>>,
+    },
+}
+
+impl<T: Clone + Integer> Matrix<T> {
+    #[allow(dead_code)]
+    pub fn zeros(num_rows: usize, num_cols: usize) -> Self {
+        Self {
+            num_rows,
+            num_cols,
+            entries: vec![Ratio::from_integer(T::zero()); num_rows * num_cols],
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn identity(size: usize) -> Self {
+        let mut matrix = Self::zeros(size, size);
+        for i in 0..size {
+            matrix[(i, i)] = Ratio::from_integer(T::one());
+        }
+        matrix
+    }
+
+    // Panics if the rows have different lengths
+    pub fn from_rows<I, R>(rows: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: IntoIterator<Item = Ratio<T>>,
+    {
+        let mut num_rows = 0;
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.extend(row);
+            num_rows += 1;
+        }
+        let num_cols = entries.len().checked_div(num_rows).unwrap_or(0);
+        assert_eq!(num_rows * num_cols, entries.len(), "Ragged matrix rows");
+        Self {
+            num_rows,
+            num_cols,
+            entries,
+        }
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.num_cols
+    }
+
+    pub fn row(&self, row: usize) -> &[Ratio<T>] {
+        &self.entries[row * self.num_cols..(row + 1) * self.num_cols]
+    }
+
+    fn swap_rows(&mut self, row_1: usize, row_2: usize) {
+        for col in 0..self.num_cols {
+            self.entries
+                .swap(row_1 * self.num_cols + col, row_2 * self.num_cols + col);
+        }
+    }
+
+    // Puts the matrix into reduced row echelon form using Gauss-Jordan
+    // elimination. Returns the pivot column of each non-zero row, along with
+    // the factor the determinant was divided by (only meaningful for square
+    // matrices of full rank).
+    fn reduce_with_scale(&mut self) -> (Vec<usize>, Ratio<T>) {
+        let zero = Ratio::from_integer(T::zero());
+        let mut scale = Ratio::from_integer(T::one());
+        let mut pivots = Vec::new();
+        for col in 0..self.num_cols {
+            let row = pivots.len();
+            if row == self.num_rows {
+                break;
+            }
+            let Some(pivot_row) =
+                (row..self.num_rows).find(|&r| self[(r, col)] != zero)
+            else {
+                continue;
+            };
+            if pivot_row != row {
+                self.swap_rows(row, pivot_row);
+                scale = zero.clone() - scale;
+            }
+
+            let pivot = self[(row, col)].clone();
+            for c in col..self.num_cols {
+                self[(row, c)] = self[(row, c)].clone() / pivot.clone();
+            }
+            scale = scale * pivot;
+
+            for other_row in 0..self.num_rows {
+                let factor = self[(other_row, col)].clone();
+                if other_row == row || factor == zero {
+                    continue;
+                }
+                for c in col..self.num_cols {
+                    let delta = factor.clone() * self[(row, c)].clone();
+                    self[(other_row, c)] = self[(other_row, c)].clone() - delta;
+                }
+            }
+            pivots.push(col);
+        }
+        (pivots, scale)
+    }
+
+    // Puts the matrix into reduced row echelon form, returning the pivot
+    // column of each non-zero row
+    #[allow(dead_code)]
+    pub fn reduce(&mut self) -> Vec<usize> {
+        self.reduce_with_scale().0
+    }
+
+    #[allow(dead_code)]
+    pub fn rank(&self) -> usize {
+        self.clone().reduce().len()
+    }
+
+    // Returns None if the matrix isn't square
+    #[allow(dead_code)]
+    pub fn determinant(&self) -> Option<Ratio<T>> {
+        if self.num_rows != self.num_cols {
+            return None;
+        }
+        let (pivots, scale) = self.clone().reduce_with_scale();
+        if pivots.len() < self.num_rows {
+            Some(Ratio::from_integer(T::zero()))
+        } else {
+            Some(scale)
+        }
+    }
+
+    // Reads a basis for the null space off a reduced matrix: one vector per
+    // free (non-pivot) column among the first `num_vars` columns
+    fn null_space_of_reduced(
+        &self,
+        pivots: &[usize],
+        num_vars: usize,
+    ) -> Vec<Vec<Ratio<T>>> {
+        let zero = Ratio::from_integer(T::zero());
+        let mut is_pivot = vec![false; num_vars];
+        for &pivot in pivots {
+            is_pivot[pivot] = true;
+        }
+        (0..num_vars)
+            .filter(|&free| !is_pivot[free])
+            .map(|free| {
+                let mut vector = vec![zero.clone(); num_vars];
+                vector[free] = Ratio::from_integer(T::one());
+                for (row, &pivot) in pivots.iter().enumerate() {
+                    vector[pivot] = zero.clone() - self[(row, free)].clone();
+                }
+                vector
+            })
+            .collect()
+    }
+
+    // Returns a basis for the vectors x such that self * x = 0
+    #[allow(dead_code)]
+    pub fn null_space(&self) -> Vec<Vec<Ratio<T>>> {
+        let mut reduced = self.clone();
+        let pivots = reduced.reduce();
+        reduced.null_space_of_reduced(&pivots, self.num_cols)
+    }
+
+    // Solves self * x = rhs. Panics if rhs has the wrong length.
+    pub fn solve(&self, rhs: &[Ratio<T>]) -> Solution<T> {
+        assert_eq!(rhs.len(), self.num_rows, "Wrong right-hand side length");
+        let mut augmented = Self::from_rows(
+            (0..self.num_rows)
+                .map(|row| self.row(row).iter().chain([&rhs[row]]).cloned()),
+        );
+        if self.num_rows == 0 {
+            augmented.num_cols = self.num_cols + 1;
+        }
+        let (pivots, _) = augmented.reduce_with_scale();
+        if pivots.last() == Some(&self.num_cols) {
+            return Solution::None;
+        }
+
+        let mut particular =
+            vec![Ratio::from_integer(T::zero()); self.num_cols];
+        for (row, &pivot) in pivots.iter().enumerate() {
+            particular[pivot] = augmented[(row, self.num_cols)].clone();
+        }
+        let null_space =
+            augmented.null_space_of_reduced(&pivots, self.num_cols);
+        if null_space.is_empty() {
+            Solution::Unique(particular)
+        } else {
+            Solution::Infinite {
+                particular,
+                null_space,
+            }
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = Ratio<T>;
+
+    fn index(&self, (row, col): (usize, usize)) -> &Ratio<T> {
+        assert!(row < self.num_rows && col < self.num_cols);
+        &self.entries[row * self.num_cols + col]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut Ratio<T> {
+        assert!(row < self.num_rows && col < self.num_cols);
+        &mut self.entries[row * self.num_cols + col]
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MemoStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl MemoStats {
+    #[allow(dead_code)]
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+// A memo table for recursive functions, keyed by the function's state
+pub struct Memo<K, V> {
+    cache: HashMap<K, V>,
+    // Once this many results are stored, new results are no longer stored
+    max_entries: Option<usize>,
+    stats: MemoStats,
+}
+
+impl<K: Hash + Eq, V: Clone> Memo<K, V> {
+    pub fn new() -> Self {
+        Self {
+            cache: HashMap::new(),
+            max_entries: None,
+            stats: MemoStats::default(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_max_entries(max_entries: usize) -> Self {
+        Self {
+            max_entries: Some(max_entries),
+            ..Self::new()
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        let result = self.cache.get(key).cloned();
+        if result.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+        result
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        if self
+            .max_entries
+            .is_none_or(|max_entries| self.cache.len() < max_entries)
+        {
+            self.cache.insert(key, value);
+        }
+    }
+
+    // Returns the stored result for `key`, or computes and stores it.
+    // `compute` is given the memo back so that it can recurse through it.
+    pub fn get_or_compute<F>(&mut self, key: K, compute: F) -> V
+    where
+        F: FnOnce(&mut Self) -> V,
+    {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = compute(self);
+        self.insert(key, value.clone());
+        value
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+
+    #[allow(dead_code)]
+    pub fn stats(&self) -> MemoStats {
+        self.stats
+    }
+}
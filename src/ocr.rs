@@ -0,0 +1,180 @@
+// Reading the capital letters that some puzzles draw on a screen as their
+// answer. There are two fonts: letters 4 pixels wide and 6 high (except for I,
+// which is 3 wide, and Y, which is 5 wide), and letters 6 wide and 10 high.
+
+const SMALL_FONT: &[(char, &str)] = &[
+    ('A', ".##. #..# #..# #### #..# #..#"),
+    ('B', "###. #..# ###. #..# #..# ###."),
+    ('C', ".##. #..# #... #... #..# .##."),
+    ('E', "#### #... ###. #... #... ####"),
+    ('F', "#### #... ###. #... #... #..."),
+    ('G', ".##. #..# #... #.## #..# .###"),
+    ('H', "#..# #..# #### #..# #..# #..#"),
+    ('I', "### .#. .#. .#. .#. ###"),
+    ('J', "..## ...# ...# ...# #..# .##."),
+    ('K', "#..# #.#. ##.. #.#. #.#. #..#"),
+    ('L', "#... #... #... #... #... ####"),
+    ('O', ".##. #..# #..# #..# #..# .##."),
+    ('P', "###. #..# #..# ###. #... #..."),
+    ('R', "###. #..# #..# ###. #.#. #..#"),
+    ('S', ".### #... #... .##. ...# ###."),
+    ('U', "#..# #..# #..# #..# #..# .##."),
+    ('Y', "#...# #...# .#.#. ..#.. ..#.. ..#.."),
+    ('Z', "#### ...# ..#. .#.. #... ####"),
+];
+
+const LARGE_FONT: &[(char, &str)] = &[
+    (
+        'A',
+        "..##.. .#..#. #....# #....# #....# ###### #....# #....# #....# \
+         #....#",
+    ),
+    (
+        'B',
+        "#####. #....# #....# #....# #####. #....# #....# #....# #....# \
+         #####.",
+    ),
+    (
+        'C',
+        ".####. #....# #..... #..... #..... #..... #..... #..... #....# \
+         .####.",
+    ),
+    (
+        'E',
+        "###### #..... #..... #..... #####. #..... #..... #..... #..... \
+         ######",
+    ),
+    (
+        'F',
+        "###### #..... #..... #..... #####. #..... #..... #..... #..... \
+         #.....",
+    ),
+    (
+        'G',
+        ".####. #....# #..... #..... #..... #..### #....# #....# #...## \
+         .###.#",
+    ),
+    (
+        'H',
+        "#....# #....# #....# #....# ###### #....# #....# #....# #....# \
+         #....#",
+    ),
+    (
+        'J',
+        "...### ....#. ....#. ....#. ....#. ....#. ....#. #...#. #...#. \
+         .###..",
+    ),
+    (
+        'K',
+        "#....# #...#. #..#.. #.#... ##.... ##.... #.#... #..#.. #...#. \
+         #....#",
+    ),
+    (
+        'L',
+        "#..... #..... #..... #..... #..... #..... #..... #..... #..... \
+         ######",
+    ),
+    (
+        'N',
+        "#....# ##...# ##...# #.#..# #.#..# #..#.# #..#.# #...## #...## \
+         #....#",
+    ),
+    (
+        'P',
+        "#####. #....# #....# #....# #####. #..... #..... #..... #..... \
+         #.....",
+    ),
+    (
+        'R',
+        "#####. #....# #....# #....# #####. #..#.. #...#. #...#. #....# \
+         #....#",
+    ),
+    (
+        'X',
+        "#....# #....# .#..#. .#..#. ..##.. ..##.. .#..#. .#..#. #....# \
+         #....#",
+    ),
+    (
+        'Z',
+        "###### .....# .....# ....#. ...#.. ..#... .#.... #..... #..... \
+         ######",
+    ),
+];
+
+// The pixels as lines of '#' (on) and '.' (off)
+pub fn render(pixels: &[Vec<bool>]) -> String {
+    pixels
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&is_on| if is_on { '#' } else { '.' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// A glyph from the fonts without any blank columns on either side, which is
+// how it's cut out of the screen
+fn trimmed(glyph: &str) -> String {
+    let rows = glyph.split(' ').collect::<Vec<_>>();
+    let is_used = |col: usize| rows.iter().any(|row| &row[col..=col] == "#");
+    let width = rows[0].len();
+    let (Some(first), Some(last)) = (
+        (0..width).find(|&col| is_used(col)),
+        (0..width).rfind(|&col| is_used(col)),
+    ) else {
+        return String::new();
+    };
+    rows.iter()
+        .map(|row| &row[first..=last])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Reads the letters drawn in the pixels, which are split wherever a column is
+// entirely off. If anything isn't a known letter, returns the drawing instead
+// so that it can be read by eye.
+pub fn read_letters(pixels: &[Vec<bool>]) -> String {
+    let is_blank_row = |row: &Vec<bool>| !row.contains(&true);
+    let first_row = pixels.iter().position(|row| !is_blank_row(row));
+    let last_row = pixels.iter().rposition(|row| !is_blank_row(row));
+    let (Some(first_row), Some(last_row)) = (first_row, last_row) else {
+        return render(pixels);
+    };
+    let rows = &pixels[first_row..=last_row];
+    let font = match rows.len() {
+        6 => SMALL_FONT,
+        10 => LARGE_FONT,
+        _ => return render(pixels),
+    };
+
+    let is_on = |row: &Vec<bool>, col: usize| row.get(col) == Some(&true);
+    let is_blank_col = |col: usize| !rows.iter().any(|row| is_on(row, col));
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut letters = String::new();
+    let mut col = 0;
+    while col < width {
+        if is_blank_col(col) {
+            col += 1;
+            continue;
+        }
+        let start = col;
+        while col < width && !is_blank_col(col) {
+            col += 1;
+        }
+        let glyph = render(
+            &rows
+                .iter()
+                .map(|row| (start..col).map(|col| is_on(row, col)).collect())
+                .collect::<Vec<_>>(),
+        )
+        .replace('\n', " ");
+        match font.iter().find(|&&(_, known)| trimmed(known) == glyph) {
+            Some(&(letter, _)) => letters.push(letter),
+            None => return render(pixels),
+        }
+    }
+    letters
+}
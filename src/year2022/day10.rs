@@ -3,6 +3,7 @@ use std::io;
 use std::io::BufRead;
 
 use crate::errors::invalid_input;
+use crate::ocr::read_letters;
 use crate::part::Part;
 
 struct MachineState {
@@ -45,25 +46,21 @@ impl MachineState {
         }
     }
 
-    pub fn print_crt(&self) {
+    // The pixels drawn during each cycle, in rows of the screen's width
+    pub fn crt_pixels(&self) -> Vec<Vec<bool>> {
         const WIDTH: i64 = 40;
 
-        for cycle_count in 1..=self.cycle_count {
-            let x_position = (cycle_count - 1) % WIDTH;
-
-            if cycle_count != 0 && x_position == 0 {
-                println!();
-            }
-
-            let x_register_value = self.x_history[&cycle_count];
-
-            if (x_register_value - x_position).abs() <= 1 {
-                print!("#");
-            } else {
-                print!(".");
-            }
-        }
-        println!();
+        let pixels = (1..self.cycle_count)
+            .map(|cycle_count| {
+                let x_position = (cycle_count - 1) % WIDTH;
+                let x_register_value = self.x_history[&cycle_count];
+                (x_register_value - x_position).abs() <= 1
+            })
+            .collect::<Vec<_>>();
+        pixels
+            .chunks(WIDTH as usize)
+            .map(<[bool]>::to_vec)
+            .collect()
     }
 }
 
@@ -125,7 +122,7 @@ pub fn run<R: io::Read>(
             println!("{}", signal_strengths.into_iter().sum::<i64>());
         }
         Part::Part2 => {
-            state.print_crt();
+            println!("{}", read_letters(&state.crt_pixels()));
         }
     }
 
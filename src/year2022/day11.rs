@@ -4,6 +4,7 @@ use std::io::BufRead;
 
 use crate::errors::invalid_input;
 use crate::iter::n_elements;
+use crate::math::lcm;
 use crate::part::Part;
 
 enum Operator {
@@ -233,7 +234,7 @@ pub fn run<R: io::Read>(
     let worry_modulus = monkeys
         .values()
         .map(|monkey| monkey.test.divisibility_check)
-        .product::<u64>();
+        .fold(1, lcm);
 
     let num_rounds: usize = match part {
         Part::Part1 => 20,
@@ -1,103 +1,65 @@
-use std::collections::LinkedList;
 use std::io;
 use std::io::BufRead;
-use std::ops::Sub;
+use std::ops::{Range, RangeInclusive};
 use std::str::FromStr;
 
+use crate::cellmap::Position;
 use crate::errors::invalid_input;
+use crate::interval_set::IntervalSet;
 use crate::part::Part;
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
-struct Vector2D {
-    pub x: i64,
-    pub y: i64,
-}
-
-impl Vector2D {
-    fn manhattan_len(self) -> i64 {
-        self.x.abs() + self.y.abs()
-    }
-
-    fn manhattan_distance(self, other: Self) -> i64 {
-        (self - other).manhattan_len()
-    }
-}
-
-impl Sub for Vector2D {
-    type Output = Self;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-        }
-    }
-}
-
-impl FromStr for Vector2D {
-    type Err = io::Error;
-
-    fn from_str(string: &str) -> io::Result<Self> {
-        let [x_text, y_text] = &string.split(", ").collect::<Vec<_>>()[..]
-        else {
-            Err(invalid_input("Could not split by \", \" into 2 components"))?
-        };
+// Parses "x=<x>, y=<y>"
+fn parse_position(string: &str) -> io::Result<Position> {
+    let [x_text, y_text] = &string.split(", ").collect::<Vec<_>>()[..] else {
+        Err(invalid_input("Could not split by \", \" into 2 components"))?
+    };
 
-        let x = x_text
-            .strip_prefix("x=")
-            .ok_or_else(|| invalid_input("Expected \"x=\""))?
-            .parse::<i64>()
-            .map_err(invalid_input)?;
+    let col = x_text
+        .strip_prefix("x=")
+        .ok_or_else(|| invalid_input("Expected \"x=\""))?
+        .parse::<isize>()
+        .map_err(invalid_input)?;
 
-        let y = y_text
-            .strip_prefix("y=")
-            .ok_or_else(|| invalid_input("Expected \"y=\""))?
-            .parse::<i64>()
-            .map_err(invalid_input)?;
+    let row = y_text
+        .strip_prefix("y=")
+        .ok_or_else(|| invalid_input("Expected \"y=\""))?
+        .parse::<isize>()
+        .map_err(invalid_input)?;
 
-        Ok(Self { x, y })
-    }
+    Ok(Position { row, col })
 }
 
 struct Sensor {
-    pub location: Vector2D,
-    pub closest_beacon_location: Vector2D,
+    pub location: Position,
+    pub closest_beacon_location: Position,
 }
 
 impl Sensor {
-    pub fn no_beacon_segment(&self, row_y: i64) -> Option<HorizontalSegment> {
-        self.no_distress_beacon_segment(row_y).map(|segment| {
-            let mut segment = segment;
-            if self.closest_beacon_location.y == row_y {
-                segment.len -= 1;
-                if self.closest_beacon_location.x < self.location.x {
-                    segment.x += 1;
-                }
-            }
-            segment
-        })
+    // How far the sensor can see: the distance to its closest beacon
+    fn range(&self) -> isize {
+        self.location
+            .manhattan_distance(self.closest_beacon_location)
     }
 
-    pub fn no_distress_beacon_segment(
-        &self,
-        row_y: i64,
-    ) -> Option<HorizontalSegment> {
-        let max_distance = self
-            .location
-            .manhattan_distance(self.closest_beacon_location);
+    fn covers(&self, point: Position) -> bool {
+        self.location.manhattan_distance(point) <= self.range()
+    }
 
-        let distance_to_row = (self.location.y - row_y).abs();
+    // The x values in a row that are no further from the sensor than its
+    // closest beacon
+    pub fn coverage(&self, row_y: isize) -> Option<Range<isize>> {
+        let distance_to_row = (self.location.row - row_y).abs();
 
-        let no_beacon_radius_in_row = max_distance - distance_to_row;
+        let radius_in_row = self.range() - distance_to_row;
 
-        if no_beacon_radius_in_row < 0 {
+        if radius_in_row < 0 {
             return None;
         }
 
-        let x = self.location.x - no_beacon_radius_in_row;
-        let len = no_beacon_radius_in_row * 2 + 1;
-
-        Some(HorizontalSegment { x, len })
+        Some(
+            (self.location.col - radius_in_row)
+                ..(self.location.col + radius_in_row + 1),
+        )
     }
 }
 
@@ -113,13 +75,13 @@ impl FromStr for Sensor {
 
         let location = sensor_location_text
             .strip_prefix("Sensor at ")
-            .ok_or_else(|| invalid_input("Expected \"Sensor at \""))?
-            .parse::<Vector2D>()?;
+            .ok_or_else(|| invalid_input("Expected \"Sensor at \""))
+            .and_then(parse_position)?;
 
         let closest_beacon_location = beacon_location_text
             .strip_prefix("closest beacon is at ")
-            .ok_or_else(|| invalid_input("Expected \"closest beacon is at \""))?
-            .parse::<Vector2D>()?;
+            .ok_or_else(|| invalid_input("Expected \"closest beacon is at \""))
+            .and_then(parse_position)?;
 
         Ok(Self {
             location,
@@ -128,133 +90,125 @@ impl FromStr for Sensor {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-struct HorizontalSegment {
-    pub x: i64,
-    pub len: i64,
+fn coverage(sensors: &[Sensor], row_y: isize) -> IntervalSet<isize> {
+    sensors
+        .iter()
+        .filter_map(|sensor| sensor.coverage(row_y))
+        .collect()
 }
 
-struct LineSegments {
-    segments: LinkedList<HorizontalSegment>,
+fn part_1(sensors: Vec<Sensor>) -> io::Result<()> {
+    const ROW_Y: isize = 2_000_000;
+
+    let beacons_in_row = sensors
+        .iter()
+        .map(|sensor| sensor.closest_beacon_location)
+        .filter(|beacon| beacon.row == ROW_Y)
+        .map(|beacon| beacon.col..(beacon.col + 1))
+        .collect::<IntervalSet<_>>();
+
+    let no_beacons_in_row =
+        coverage(&sensors, ROW_Y).difference(&beacons_in_row);
+
+    println!("{}", no_beacons_in_row.total_len::<isize>());
+
+    Ok(())
 }
 
-impl LineSegments {
-    pub fn new(segment: HorizontalSegment) -> Self {
-        Self {
-            segments: LinkedList::from([segment]),
+// The distress beacon is the only point in the search area that no sensor
+// covers, so it must be right next to the edge of a sensor's range or of the
+// area. Rotated by 45 degrees (u = x + y, v = x - y), the lines just outside
+// each sensor's range are u = c or v = c, so the candidates are where those
+// lines cross each other or the edges of the area. Points always have u and v
+// of the same parity, so the first line of points outside a range may be one
+// or two steps away from it.
+fn find_distress_beacon(
+    sensors: &[Sensor],
+    coordinates: RangeInclusive<isize>,
+) -> Option<Position> {
+    let mut u_lines = Vec::new();
+    let mut v_lines = Vec::new();
+    for sensor in sensors {
+        let Position { row: y, col: x } = sensor.location;
+        for reach in [sensor.range() + 1, sensor.range() + 2] {
+            u_lines.extend([x + y - reach, x + y + reach]);
+            v_lines.extend([x - y - reach, x - y + reach]);
         }
     }
 
-    pub fn summed_len(&self) -> i64 {
-        self.segments.iter().map(|segment| segment.len).sum::<i64>()
-    }
+    let crossings = u_lines.iter().flat_map(|&u| {
+        v_lines
+            .iter()
+            .filter(move |&&v| (u + v) % 2 == 0)
+            .map(move |&v| Position {
+                row: (u - v) / 2,
+                col: (u + v) / 2,
+            })
+    });
 
-    pub fn into_segments(self) -> LinkedList<HorizontalSegment> {
-        self.segments
-    }
+    let edges = [*coordinates.start(), *coordinates.end()];
+    let on_edges = edges.into_iter().flat_map(|edge| {
+        let on_u_lines = u_lines.iter().flat_map(move |&u| {
+            [
+                Position {
+                    row: u - edge,
+                    col: edge,
+                },
+                Position {
+                    row: edge,
+                    col: u - edge,
+                },
+            ]
+        });
+        let on_v_lines = v_lines.iter().flat_map(move |&v| {
+            [
+                Position {
+                    row: edge - v,
+                    col: edge,
+                },
+                Position {
+                    row: edge,
+                    col: v + edge,
+                },
+            ]
+        });
+        on_u_lines.chain(on_v_lines)
+    });
 
-    pub fn remove(&mut self, to_remove: HorizontalSegment) {
-        let mut cursor = self.segments.cursor_front_mut();
-        while !cursor.index().is_none() {
-            let segment = *cursor.current().unwrap();
-
-            if segment.x + segment.len <= to_remove.x {
-                cursor.move_next();
-                continue;
-            }
-
-            if to_remove.x + to_remove.len <= segment.x {
-                break;
-            }
-
-            // We have stuff to remove
-            let first_segment = if segment.x < to_remove.x {
-                let leftover_beginning_len = to_remove.x - segment.x;
-                Some(HorizontalSegment {
-                    x: segment.x,
-                    len: leftover_beginning_len,
-                })
-            } else {
-                None
-            };
-
-            let second_segment =
-                if to_remove.x + to_remove.len < segment.x + segment.len {
-                    let leftover_end_len =
-                        segment.x + segment.len - (to_remove.x + to_remove.len);
-                    Some(HorizontalSegment {
-                        x: to_remove.x + to_remove.len,
-                        len: leftover_end_len,
-                    })
-                } else {
-                    None
-                };
-
-            cursor.remove_current(); // Also moves cursor to next node
-
-            for segment in [first_segment, second_segment] {
-                if let Some(segment) = segment {
-                    cursor.insert_before(segment);
-                }
-            }
-        }
-    }
+    let corners = edges
+        .into_iter()
+        .flat_map(|x| edges.map(|y| Position { row: y, col: x }));
+
+    crossings.chain(on_edges).chain(corners).find(|&point| {
+        coordinates.contains(&point.col)
+            && coordinates.contains(&point.row)
+            && !sensors.iter().any(|sensor| sensor.covers(point))
+    })
 }
 
-fn part_1(sensors: Vec<Sensor>) -> io::Result<()> {
-    const ROW_Y: i64 = 2_000_000;
+const PUZZLE_SEARCH_COORDINATES: RangeInclusive<isize> = 0..=4_000_000;
 
-    const ROW_X: i64 = -5_000_000;
-    const ROW_LEN: i64 = 10_000_000;
+const EXAMPLE_SEARCH_COORDINATES: RangeInclusive<isize> = 0..=20;
 
-    let mut possible_beacons_in_row = LineSegments::new(HorizontalSegment {
-        x: ROW_X,
-        len: ROW_LEN,
+// The example's sensors are all near the origin, and it searches a much
+// smaller area than the puzzle
+fn search_coordinates(sensors: &[Sensor]) -> RangeInclusive<isize> {
+    let is_example = sensors.iter().all(|sensor| {
+        sensor.location.row.abs() < 1_000 && sensor.location.col.abs() < 1_000
     });
-
-    for sensor in sensors {
-        if let Some(segment) = sensor.no_beacon_segment(ROW_Y) {
-            possible_beacons_in_row.remove(segment);
-        }
+    if is_example {
+        EXAMPLE_SEARCH_COORDINATES
+    } else {
+        PUZZLE_SEARCH_COORDINATES
     }
-
-    println!("{}", ROW_LEN - possible_beacons_in_row.summed_len());
-
-    Ok(())
 }
 
 fn part_2(sensors: Vec<Sensor>) -> io::Result<()> {
-    const MIN_X: i64 = 0;
-    const MAX_X: i64 = 4_000_001;
-
-    const MIN_Y: i64 = 0;
-    const MAX_Y: i64 = 4_000_001;
-
-    for row_y in MIN_Y..=MAX_Y {
-        let mut possible_beacons_in_row =
-            LineSegments::new(HorizontalSegment {
-                x: MIN_X,
-                len: MAX_X - MIN_X,
-            });
-
-        for sensor in &sensors {
-            if let Some(segment) = sensor.no_distress_beacon_segment(row_y) {
-                possible_beacons_in_row.remove(segment);
-            }
-        }
-
-        if possible_beacons_in_row.summed_len() != 0 {
-            let segments = possible_beacons_in_row.into_segments();
-            let mut possible_scores = Vec::<i64>::new();
+    let beacon =
+        find_distress_beacon(&sensors, search_coordinates(&sensors))
+            .ok_or_else(|| invalid_input("No room for the distress beacon"))?;
 
-            for segment in segments {
-                for x in segment.x..(segment.x + segment.len) {
-                    possible_scores.push(x * 4_000_000 + row_y);
-                }
-            }
-            println!("y = {}: possible scores: {:?}", row_y, possible_scores);
-        }
-    }
+    println!("{}", beacon.col * 4_000_000 + beacon.row);
 
     Ok(())
 }
@@ -1,9 +1,10 @@
-use std::cmp::max;
-use std::collections::HashMap;
+use std::cmp::Reverse;
 use std::io;
 use std::io::BufRead;
 
+use crate::branch_and_bound::{BranchAndBound, Problem};
 use crate::errors::invalid_input;
+use crate::graph::Graph;
 use crate::part::Part;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -12,63 +13,51 @@ struct ValveID(usize);
 #[derive(Debug)]
 struct Valve {
     flow_rate: u64,
-    tunnels: Vec<ValveID>,
     optimal_paths: Vec<usize>,
 }
 
 struct Plumbing {
     valves: Vec<Valve>,
     starting_valve: ValveID,
+    // The valves with a non-zero flow rate, by decreasing flow rate
+    useful_valves: Vec<ValveID>,
 }
 
-#[derive(Clone, Debug)]
-struct MRPLocation {
-    distance_to_location: usize,
+#[derive(Clone, Copy, Debug)]
+struct Actor {
     valve_id: ValveID,
+    minutes_remaining: u64,
 }
 
 #[derive(Clone, Debug)]
 struct MRPState {
-    minutes_remaining: u64,
-    open_valves: Vec<bool>,
-    pressure_per_minute: u64,
+    // Without the elephant, its actor never has any time
+    actors: [Actor; 2],
+    // Bit i is set once useful_valves[i] is open
+    open_valves: u64,
+    // Including all the pressure that the open valves will release in time
     released_pressure: u64,
-    my_location: MRPLocation,
-    elephant_location: Option<MRPLocation>,
-    max_seen: u64,
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum ValveOpener {
-    Me,
-    Elephant,
 }
 
-impl ValveOpener {
-    pub fn next(self) -> Self {
-        match self {
-            Self::Me => Self::Elephant,
-            Self::Elephant => Self::Me,
-        }
-    }
+#[derive(Clone, Copy, Debug)]
+enum MRPMove {
+    // The actor walks to a useful valve and opens it
+    Open { actor: usize, useful_index: usize },
+    // The actor does nothing more, leaving the rest to the other actor
+    Stop { actor: usize },
 }
 
 impl MRPState {
-    pub fn is_open(&self, valve_id: ValveID) -> bool {
-        self.open_valves[valve_id.0]
+    fn is_open(&self, useful_index: usize) -> bool {
+        self.open_valves & (1 << useful_index) != 0
     }
 
-    pub fn all_valves_open(&self) -> bool {
-        self.open_valves.iter().all(|open| *open)
-    }
-
-    pub fn location(
-        &mut self,
-        opener: ValveOpener,
-    ) -> Option<&mut MRPLocation> {
-        match opener {
-            ValveOpener::Me => Some(&mut self.my_location),
-            ValveOpener::Elephant => self.elephant_location.as_mut(),
+    // The actor with the most time left moves next
+    fn next_actor(&self) -> usize {
+        if self.actors[1].minutes_remaining > self.actors[0].minutes_remaining {
+            1
+        } else {
+            0
         }
     }
 }
@@ -78,222 +67,161 @@ impl Plumbing {
         &self.valves[id.0]
     }
 
-    // Should be called after pressure is processed but before action is taken.
-    fn total_pressure_released_upper_bound(&self, state: &MRPState) -> u64 {
-        let mut remaining_flow_rates = self
-            .valves
-            .iter()
-            .enumerate()
-            .filter(|(i, _)| !state.open_valves[*i])
-            .map(|(_, valve)| valve.flow_rate)
-            .collect::<Vec<_>>();
-
-        remaining_flow_rates.sort_unstable();
-
-        let mut max_pressure = state.released_pressure;
-        let mut ppm = state.pressure_per_minute;
-
-        let mut my_dist = state.my_location.distance_to_location;
-        let mut elephant_dist = state
-            .elephant_location
-            .as_ref()
-            .map(|loc| loc.distance_to_location)
-            .unwrap_or(0);
-
-        let is_elephant = state.elephant_location.is_some();
-
-        for _ in 0..state.minutes_remaining {
-            // Take my action
-            if my_dist != 0 {
-                my_dist -= 1;
-            } else {
-                if let Some(flow_rate) = remaining_flow_rates.pop() {
-                    ppm += flow_rate;
-                    my_dist = 1;
-                }
-            }
-
-            // Take elephant's action (if applicable)
-            if is_elephant {
-                if elephant_dist != 0 {
-                    elephant_dist -= 1;
-                } else {
-                    if let Some(flow_rate) = remaining_flow_rates.pop() {
-                        ppm += flow_rate;
-                        elephant_dist = 1;
-                    }
-                }
-            }
-
-            // Release pressure
-            max_pressure += ppm;
-        }
-
-        max_pressure
+    fn distance(&self, from: ValveID, to: ValveID) -> u64 {
+        self.get_valve(from).optimal_paths[to.0] as u64
     }
 
     pub fn most_released_pressure(
         &self,
         minutes: u64,
         is_elephant: bool,
-    ) -> u64 {
-        let elephant_location = if is_elephant {
-            Some(MRPLocation {
-                valve_id: self.starting_valve,
-                distance_to_location: 0,
-            })
-        } else {
-            None
-        };
+    ) -> io::Result<u64> {
+        if self.useful_valves.len() > 64 {
+            return Err(invalid_input("Too many valves with a flow rate"));
+        }
 
-        let mut state = MRPState {
-            minutes_remaining: minutes,
-            open_valves: vec![false; self.valves.len()],
-            pressure_per_minute: 0,
+        let actor = |minutes_remaining| Actor {
+            valve_id: self.starting_valve,
+            minutes_remaining,
+        };
+        let state = MRPState {
+            actors: [
+                actor(minutes),
+                actor(if is_elephant { minutes } else { 0 }),
+            ],
+            open_valves: 0,
             released_pressure: 0,
-            my_location: MRPLocation {
-                valve_id: self.starting_valve,
-                distance_to_location: 0,
-            },
-            elephant_location,
-            max_seen: 0,
         };
 
-        self.mrp_helper(&mut state, ValveOpener::Me);
-
-        state.max_seen
+        let outcome = BranchAndBound::new(self).maximize(state);
+        Ok(outcome.best.unwrap_or(0))
     }
+}
 
-    fn mrp_helper(&self, state: &mut MRPState, current_actor: ValveOpener) {
-        // TODO: Use a proper defer or something like that.
-
-        // I act first. Before I act, process a minute's worth of pressure.
-        if current_actor == ValveOpener::Me {
-            state.minutes_remaining -= 1;
-            state.released_pressure += state.pressure_per_minute;
-
-            if state.minutes_remaining == 0 {
-                state.max_seen = max(state.max_seen, state.released_pressure);
-                state.minutes_remaining += 1;
-                state.released_pressure -= state.pressure_per_minute;
-                return;
-            }
-
-            if state.all_valves_open() {
-                let result = state.released_pressure
-                    + state.pressure_per_minute * state.minutes_remaining;
-                state.max_seen = max(state.max_seen, result);
-
-                if current_actor == ValveOpener::Me {
-                    state.minutes_remaining += 1;
-                    state.released_pressure -= state.pressure_per_minute;
-                }
-                return;
-            }
+impl Problem for Plumbing {
+    type State = MRPState;
+    type Move = MRPMove;
+    type Value = u64;
+
+    fn moves(&self, state: &MRPState) -> Vec<MRPMove> {
+        let actor = state.next_actor();
+        let Actor {
+            valve_id,
+            minutes_remaining,
+        } = state.actors[actor];
+        if minutes_remaining == 0 {
+            return Vec::new();
         }
 
-        if self.total_pressure_released_upper_bound(state) <= state.max_seen {
-            if current_actor == ValveOpener::Me {
-                state.minutes_remaining += 1;
-                state.released_pressure -= state.pressure_per_minute;
-            }
-            return;
-        }
+        let mut moves = (0..self.useful_valves.len())
+            .filter(|&useful_index| {
+                !state.is_open(useful_index)
+                    && self.distance(valve_id, self.useful_valves[useful_index])
+                        < minutes_remaining - 1
+            })
+            .map(|useful_index| MRPMove::Open {
+                actor,
+                useful_index,
+            })
+            .collect::<Vec<_>>();
+        moves.push(MRPMove::Stop { actor });
+        moves
+    }
 
-        let Some(location) = state.location(current_actor) else {
-            self.mrp_helper(state, current_actor.next());
-            if current_actor == ValveOpener::Me {
-                state.minutes_remaining += 1;
-                state.released_pressure -= state.pressure_per_minute;
+    fn apply(&self, state: &MRPState, mv: &MRPMove) -> MRPState {
+        let mut state = state.clone();
+        match *mv {
+            MRPMove::Open {
+                actor,
+                useful_index,
+            } => {
+                let to = self.useful_valves[useful_index];
+                let actor = &mut state.actors[actor];
+                actor.minutes_remaining -=
+                    self.distance(actor.valve_id, to) + 1;
+                actor.valve_id = to;
+                state.open_valves |= 1 << useful_index;
+                state.released_pressure +=
+                    self.get_valve(to).flow_rate * actor.minutes_remaining;
             }
-            return;
-        };
-
-        let distance = location.distance_to_location;
-        let current_valve_id = location.valve_id;
-
-        if distance != 0 {
-            // In the process of moving; continue doing so.
-            state.location(current_actor).unwrap().distance_to_location -= 1;
-
-            self.mrp_helper(state, current_actor.next());
-
-            state.location(current_actor).unwrap().distance_to_location += 1;
-
-            if current_actor == ValveOpener::Me {
-                state.minutes_remaining += 1;
-                state.released_pressure -= state.pressure_per_minute;
+            MRPMove::Stop { actor } => {
+                state.actors[actor].minutes_remaining = 0
             }
-            return;
         }
+        state
+    }
 
-        let valve = self.get_valve(current_valve_id);
-        if valve.flow_rate != 0 && !state.is_open(current_valve_id) {
-            // Try opening the valve.
-            state.open_valves[current_valve_id.0] = true;
-            state.pressure_per_minute += valve.flow_rate;
+    fn objective(&self, state: &MRPState) -> Option<u64> {
+        Some(state.released_pressure)
+    }
 
-            self.mrp_helper(state, current_actor.next());
+    // Each actor can open its first valve no sooner than the nearest closed
+    // valve allows, and every later valve at least two minutes after that.
+    // Pairing the largest flow rates with the earliest of those times can
+    // only overestimate.
+    fn bound(&self, state: &MRPState) -> u64 {
+        let closed = (0..self.useful_valves.len())
+            .filter(|&useful_index| !state.is_open(useful_index))
+            .map(|useful_index| self.useful_valves[useful_index])
+            .collect::<Vec<_>>();
 
-            state.pressure_per_minute -= valve.flow_rate;
-            state.open_valves[current_valve_id.0] = false;
-        } else {
-            // Try moving to other closed, non-zero-flow-rate valves.
-            for connected_valve_index in 0..self.valves.len() {
-                let connected_valve_id = ValveID(connected_valve_index);
-                let connected_valve = self.get_valve(connected_valve_id);
-                if !state.is_open(connected_valve_id)
-                    && connected_valve.flow_rate != 0
-                {
-                    let location = state.location(current_actor).unwrap();
-                    location.valve_id = connected_valve_id;
-                    location.distance_to_location =
-                        valve.optimal_paths[connected_valve_index] - 1;
-
-                    self.mrp_helper(state, current_actor.next());
-                }
+        let mut open_times = Vec::new();
+        for actor in &state.actors {
+            let Some(nearest) = closed
+                .iter()
+                .map(|&valve_id| self.distance(actor.valve_id, valve_id))
+                .min()
+            else {
+                break;
+            };
+            let mut minutes = actor
+                .minutes_remaining
+                .saturating_sub(nearest.saturating_add(1));
+            while minutes > 0 {
+                open_times.push(minutes);
+                minutes = minutes.saturating_sub(2);
             }
-
-            let location = state.location(current_actor).unwrap();
-            location.distance_to_location = 0;
-            location.valve_id = current_valve_id;
-        }
-
-        if current_actor == ValveOpener::Me {
-            state.minutes_remaining += 1;
-            state.released_pressure -= state.pressure_per_minute;
         }
+        open_times.sort_unstable_by(|a, b| b.cmp(a));
+
+        // The useful valves are sorted by decreasing flow rate
+        state.released_pressure
+            + closed
+                .iter()
+                .zip(open_times)
+                .map(|(&valve_id, minutes)| {
+                    self.get_valve(valve_id).flow_rate * minutes
+                })
+                .sum::<u64>()
     }
 }
 
 struct ValvesParser {
-    name_to_id: HashMap<String, ValveID>,
-    valves: Vec<Valve>,
+    tunnels: Graph,
+    flow_rates: Vec<u64>,
 }
 
 impl ValvesParser {
     pub fn new() -> Self {
         Self {
-            name_to_id: HashMap::new(),
-            valves: Vec::new(),
+            tunnels: Graph::new_directed(),
+            flow_rates: Vec::new(),
         }
     }
 
-    fn get_or_create_valve_id(&mut self, name: String) -> ValveID {
-        let get_next_id = || -> ValveID {
-            let id = self.valves.len();
-            self.valves.push(Valve {
-                flow_rate: 0,
-                tunnels: Vec::new(),
-                optimal_paths: Vec::new(),
-            });
-            ValveID(id)
-        };
-        *self.name_to_id.entry(name).or_insert_with(get_next_id)
+    fn get_or_create_valve_id(&mut self, name: &str) -> ValveID {
+        let id = self.tunnels.add_vertex(name);
+        if id == self.flow_rates.len() {
+            self.flow_rates.push(0);
+        }
+        ValveID(id)
     }
 
-    pub fn valve_id(&self, name: &str) -> ValveID {
-        self.name_to_id[name]
+    pub fn valve_id(&self, name: &str) -> io::Result<ValveID> {
+        self.tunnels
+            .id(name)
+            .map(ValveID)
+            .ok_or_else(|| invalid_input(format!("No valve named {name}")))
     }
 
     pub fn add_valve(
@@ -302,47 +230,28 @@ impl ValvesParser {
         flow_rate: u64,
         tunnel_names: Vec<&str>,
     ) {
-        let this_id = self.get_or_create_valve_id(String::from(name));
-        self.valves[this_id.0].flow_rate = flow_rate;
-
-        let tunnel_ids = tunnel_names
-            .iter()
-            .map(|name| self.get_or_create_valve_id(String::from(*name)))
-            .collect::<Vec<_>>();
-        self.valves[this_id.0].tunnels = tunnel_ids;
-    }
-
-    fn floyd_warshall(&mut self) {
-        let num_valves = self.valves.len();
-
-        for (i, valve) in self.valves.iter_mut().enumerate() {
-            valve.optimal_paths = vec![usize::MAX; num_valves];
-            valve.optimal_paths[i] = 0;
-            for tunnel in &valve.tunnels {
-                valve.optimal_paths[tunnel.0] = 1;
-            }
-        }
+        let this_id = self.get_or_create_valve_id(name);
+        self.flow_rates[this_id.0] = flow_rate;
 
-        for k in 0..num_valves {
-            for i in 0..num_valves {
-                for j in 0..num_valves {
-                    let dist_i_j = self.valves[i].optimal_paths[j];
-                    let dist_i_k = self.valves[i].optimal_paths[k];
-                    let dist_k_j = self.valves[k].optimal_paths[j];
-                    let Some(sum) = dist_i_k.checked_add(dist_k_j) else {
-                        continue;
-                    };
-                    if dist_i_j > sum {
-                        self.valves[i].optimal_paths[j] = sum;
-                    }
-                }
-            }
+        for tunnel_name in tunnel_names {
+            let tunnel_id = self.get_or_create_valve_id(tunnel_name);
+            self.tunnels.add_edge(this_id.0, tunnel_id.0);
         }
     }
 
-    pub fn into_valves(mut self) -> Vec<Valve> {
-        self.floyd_warshall();
-        self.valves
+    pub fn into_valves(self) -> Vec<Valve> {
+        self.tunnels
+            .all_pairs_shortest_paths()
+            .into_iter()
+            .zip(self.flow_rates)
+            .map(|(distances, flow_rate)| Valve {
+                flow_rate,
+                optimal_paths: distances
+                    .into_iter()
+                    .map(|distance| distance.unwrap_or(usize::MAX))
+                    .collect(),
+            })
+            .collect()
     }
 }
 
@@ -380,15 +289,22 @@ pub fn run<R: io::Read>(
         valves_parser.add_valve(valve_name, flow_rate, tunnel_names);
     }
 
-    let starting_valve = valves_parser.valve_id("AA");
+    let starting_valve = valves_parser.valve_id("AA")?;
+    let valves = valves_parser.into_valves();
+    let mut useful_valves = (0..valves.len())
+        .filter(|&i| valves[i].flow_rate != 0)
+        .map(ValveID)
+        .collect::<Vec<_>>();
+    useful_valves.sort_by_key(|id| Reverse(valves[id.0].flow_rate));
     let plumbing = Plumbing {
-        valves: valves_parser.into_valves(),
+        valves,
         starting_valve,
+        useful_valves,
     };
 
     let mrp = match part {
-        Part::Part1 => plumbing.most_released_pressure(30, false),
-        Part::Part2 => plumbing.most_released_pressure(26, true),
+        Part::Part1 => plumbing.most_released_pressure(30, false)?,
+        Part::Part2 => plumbing.most_released_pressure(26, true)?,
     };
 
     println!("{}", mrp);
@@ -4,6 +4,7 @@ use std::io;
 use std::io::BufRead;
 use std::ops::Add;
 
+use crate::cycle::metric_after;
 use crate::errors::invalid_input;
 use crate::part::Part;
 
@@ -102,9 +103,9 @@ enum Wind {
     Right,
 }
 
-struct Chamber<'a, I> {
+struct Chamber<'a> {
     width: usize,
-    winds: I,
+    winds: &'a [Wind],
     fixed_layout: VecDeque<Vec<Block>>,
     fixed_layout_y_offset: usize,
     rocks: &'a [&'a Rock],
@@ -113,8 +114,18 @@ struct Chamber<'a, I> {
     pub tower_height: usize,
 }
 
-impl<'a, I: Iterator<Item = Wind>> Chamber<'a, I> {
-    pub fn new(width: usize, winds: I, rocks: &'a [&'a Rock]) -> Self {
+// Everything that decides how the rest of the tower grows: which rock and
+// wind come next, and how far below the top of the tower each column's
+// highest rock is
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct ChamberState {
+    rock_index: usize,
+    wind_index: usize,
+    surface_profile: Vec<usize>,
+}
+
+impl<'a> Chamber<'a> {
+    pub fn new(width: usize, winds: &'a [Wind], rocks: &'a [&'a Rock]) -> Self {
         Self {
             width,
             winds,
@@ -127,6 +138,23 @@ impl<'a, I: Iterator<Item = Wind>> Chamber<'a, I> {
         }
     }
 
+    fn state(&self) -> ChamberState {
+        let top = self.tower_height - self.fixed_layout_y_offset;
+        let surface_profile = (0..self.width)
+            .map(|x| {
+                (0..top)
+                    .rev()
+                    .position(|y| self.fixed_layout[y][x] == Block::Rock)
+                    .unwrap_or(top)
+            })
+            .collect();
+        ChamberState {
+            rock_index: self.rock_num % self.rocks.len(),
+            wind_index: self.wind_movements % self.winds.len(),
+            surface_profile,
+        }
+    }
+
     fn next_rock(&mut self) -> &'a Rock {
         let rock = self.rocks[self.rock_num % self.rocks.len()];
         self.rock_num += 1;
@@ -185,27 +213,26 @@ impl<'a, I: Iterator<Item = Wind>> Chamber<'a, I> {
 
         loop {
             // Try to move with the wind
-            if let Some(wind) = self.winds.next() {
-                match wind {
-                    Wind::Left => {
-                        let mut new_location = rock_location;
-                        if new_location.x != 0 {
-                            new_location.x -= 1;
-                            if self.new_rock_can_be_at(&rock, new_location) {
-                                rock_location = new_location;
-                            }
-                        }
-                    }
-                    Wind::Right => {
-                        let mut new_location = rock_location;
-                        new_location.x += 1;
+            let wind = self.winds[self.wind_movements % self.winds.len()];
+            match wind {
+                Wind::Left => {
+                    let mut new_location = rock_location;
+                    if new_location.x != 0 {
+                        new_location.x -= 1;
                         if self.new_rock_can_be_at(&rock, new_location) {
                             rock_location = new_location;
                         }
                     }
                 }
-                self.wind_movements += 1;
+                Wind::Right => {
+                    let mut new_location = rock_location;
+                    new_location.x += 1;
+                    if self.new_rock_can_be_at(&rock, new_location) {
+                        rock_location = new_location;
+                    }
+                }
             }
+            self.wind_movements += 1;
 
             // Try to move downwards
             if rock_location.y == 0 {
@@ -260,52 +287,26 @@ pub fn run<R: io::Read>(
         &Rock::new(RockType::Square),
     ];
 
-    let mut chamber = Chamber::new(7, winds.into_iter().cycle(), &rocks);
+    if winds.is_empty() {
+        return Err(invalid_input("No winds"));
+    }
+
+    let mut chamber = Chamber::new(7, &winds, &rocks);
 
     let num_rocks: usize = match part {
         Part::Part1 => 2_022,
-        Part::Part2 => 20_000,
+        Part::Part2 => 1_000_000_000_000,
     };
 
-    let mut first_heights = Vec::<usize>::new();
-    let mut recent_heights = VecDeque::<usize>::new();
-    for i in 0..num_rocks {
-        let before_height = chamber.tower_height;
-
-        chamber.simulate_rock_fall(2);
-
-        let after_height = chamber.tower_height;
-
-        let diff = after_height - before_height;
-
-        const REPEAT_CHECK_SIZE: usize = 100;
-        if first_heights.len() < REPEAT_CHECK_SIZE {
-            const REPEAT_SAMPLE_START: usize = 5_000;
-            if i >= REPEAT_SAMPLE_START {
-                first_heights.push(diff);
-            }
-        } else {
-            recent_heights.push_back(diff);
-
-            if recent_heights.len() > first_heights.len() {
-                recent_heights.pop_front();
-
-                if recent_heights.iter().eq(first_heights.iter()) {
-                    println!(
-                        "Potential repeat of size {} before num_rocks={} height={}",
-                        REPEAT_CHECK_SIZE,
-                        i + 1,
-                        chamber.tower_height,
-                    );
-                }
-            }
-        }
-    }
-
-    println!(
-        "Total height for num_rocks={}: {}",
-        num_rocks, chamber.tower_height
+    let height = metric_after(
+        &mut chamber,
+        num_rocks,
+        |chamber| chamber.simulate_rock_fall(2),
+        Chamber::state,
+        |chamber| chamber.tower_height as i64,
     );
 
+    println!("{height}");
+
     Ok(())
 }
@@ -1,33 +1,10 @@
 use std::collections::{HashSet, VecDeque};
 use std::io;
 use std::io::BufRead;
-use std::str::FromStr;
 
-use crate::errors::invalid_input;
+use crate::geometry::Point3D;
 use crate::part::Part;
 
-#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
-struct Vec3D {
-    pub x: i64,
-    pub y: i64,
-    pub z: i64,
-}
-
-impl FromStr for Vec3D {
-    type Err = io::Error;
-
-    fn from_str(string: &str) -> io::Result<Self> {
-        let &[x, y, z] = &string
-            .split(',')
-            .map(|string| string.parse::<i64>().map_err(invalid_input))
-            .collect::<io::Result<Vec<_>>>()?[..]
-        else {
-            Err(invalid_input("Expected x,y,z"))?
-        };
-        Ok(Self { x, y, z })
-    }
-}
-
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 enum Plane {
     XY,
@@ -37,13 +14,13 @@ enum Plane {
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 struct Side {
-    pub location: Vec3D,
+    pub location: Point3D,
     pub plane: Plane,
 }
 
 #[derive(Clone, Debug)]
 struct Cube {
-    pub location: Vec3D,
+    pub location: Point3D,
 }
 
 impl Cube {
@@ -100,7 +77,7 @@ impl Cube {
         ]
     }
 
-    pub fn neighbors(&self) -> [(Side, Vec3D); 6] {
+    pub fn neighbors(&self) -> [(Side, Point3D); 6] {
         let mut location1 = self.location;
         location1.z -= 1;
 
@@ -135,7 +112,7 @@ fn part_1<R: io::Read>(reader: io::BufReader<R>) -> io::Result<()> {
     let mut connected_sides = HashSet::<Side>::new();
 
     for line in reader.lines() {
-        let point = line?.parse::<Vec3D>()?;
+        let point = line?.parse::<Point3D>()?;
         let cube = Cube { location: point };
         for side in cube.sides() {
             if connected_sides.contains(&side) {
@@ -158,18 +135,18 @@ fn part_1<R: io::Read>(reader: io::BufReader<R>) -> io::Result<()> {
 fn part_2<R: io::Read>(reader: io::BufReader<R>) -> io::Result<()> {
     let cube_locations = reader
         .lines()
-        .map(|line: io::Result<String>| -> io::Result<Vec3D> {
-            line?.parse::<Vec3D>()
+        .map(|line: io::Result<String>| -> io::Result<Point3D> {
+            line?.parse::<Point3D>()
         })
-        .collect::<io::Result<HashSet<Vec3D>>>()?;
+        .collect::<io::Result<HashSet<Point3D>>>()?;
 
     // All cubes must fit in a larger cube of side length REACHABLE_SEARCH_DIM
     // with origin reachable_starting_location.
     // There must be a layer of air just on the inside of that cube as well.
     const REACHABLE_SEARCH_DIM: i64 = 40;
-    let reachable_starting_location = Vec3D { x: 0, y: 0, z: 0 };
+    let reachable_starting_location = Point3D { x: 0, y: 0, z: 0 };
 
-    let in_range = |location: Vec3D| -> bool {
+    let in_range = |location: Point3D| -> bool {
         [location.x, location.y, location.z]
             .into_iter()
             .all(|coord| coord >= 0 && coord < REACHABLE_SEARCH_DIM)
@@ -177,9 +154,9 @@ fn part_2<R: io::Read>(reader: io::BufReader<R>) -> io::Result<()> {
 
     let mut reachable_sides = HashSet::<Side>::new();
 
-    let mut visited_locations = HashSet::<Vec3D>::new();
+    let mut visited_locations = HashSet::<Point3D>::new();
 
-    let mut locations = VecDeque::<Vec3D>::new();
+    let mut locations = VecDeque::<Point3D>::new();
     locations.push_back(reachable_starting_location);
 
     loop {
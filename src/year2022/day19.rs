@@ -1,13 +1,11 @@
 use std::cmp::max;
-use std::collections::HashMap;
 use std::io;
 use std::io::BufRead;
 
+use crate::branch_and_bound::{BranchAndBound, Problem, SearchOrder};
 use crate::errors::invalid_input;
 use crate::part::Part;
 
-// TODO: Make this solution faster
-
 const NUM_RESOURCE_TYPES: usize = 4;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -52,225 +50,119 @@ impl Blueprint {
     }
 
     pub fn max_geodes(&self, minutes: usize) -> usize {
-        let mut state_manager = StateManager {
-            cache: HashMap::new(),
-        };
-        state_manager.max_geodes(&self, minutes)
-    }
-}
-
-struct StateManager {
-    cache: HashMap<StateCacheEntry, usize>,
-}
-
-impl StateManager {
-    pub fn max_geodes(
-        &mut self,
-        blueprint: &Blueprint,
-        minutes: usize,
-    ) -> usize {
-        let mut state = State {
+        let state = State {
             minutes_remaining: minutes,
             resources: [0; NUM_RESOURCE_TYPES],
             robots: [1, 0, 0, 0],
-            pending_robots: [0; NUM_RESOURCE_TYPES],
-            maximum_geodes_seen: 0,
         };
-
-        self.max_geodes_cached(blueprint, &mut state).unwrap_or(0)
+        BranchAndBound::new(self)
+            .with_order(SearchOrder::BestFirst)
+            .maximize(state)
+            .best
+            .unwrap_or(0)
     }
 
-    fn max_geodes_cached(
-        &mut self,
-        blueprint: &Blueprint,
-        state: &mut State,
-    ) -> Option<usize> {
-        let cache_entry = state.cache_entry();
-
-        let cache_value = self.cache.get(&cache_entry);
-
-        match cache_value {
-            None => {
-                let result_to_cache =
-                    self.max_geodes_uncached(blueprint, state);
-                if let Some(value) = result_to_cache {
-                    self.cache.insert(cache_entry, value);
-                }
-
-                result_to_cache
-            }
-            Some(value) => Some(*value),
+    // Building more robots of a type than the most that any robot costs of
+    // that resource can't help, since only one robot is built per minute
+    fn max_useful_robots(&self, robot_type: ResourceType) -> usize {
+        if robot_type == ResourceType::Geode {
+            return usize::MAX;
         }
+        (0..NUM_RESOURCE_TYPES)
+            .map(|i| self.costs(ResourceType::from_number(i).unwrap()))
+            .map(|costs| costs[robot_type as usize])
+            .max()
+            .unwrap_or(0)
     }
 
-    // Call this right after ticking a minute
-    fn max_geodes_upper_bound(
+    // The minutes until a robot of this type can be built with the current
+    // robots, including the minute it takes to build
+    fn minutes_to_build(
         &self,
-        _blueprint: &Blueprint,
         state: &State,
-    ) -> usize {
-        if state.minutes_remaining == 0 {
-            return 0;
-        }
-
-        // Best case: build a geode robot every minute
-        let mut geodes = 0;
-        let mut num_geode_robots = state.robots[ResourceType::Geode as usize];
-        // Wait a minute for the first robot to be available
-        geodes += num_geode_robots;
-        for _ in 0..(state.minutes_remaining - 1) {
-            // Build a geode robot
-            num_geode_robots += 1;
-            // Tick a minute
-            geodes += num_geode_robots;
-        }
-        geodes
-    }
-
-    fn max_geodes_uncached(
-        &mut self,
-        blueprint: &Blueprint,
-        state: &mut State,
-    ) -> Option<usize> {
-        let pending = state.pending_robots.clone();
-        let starting_geode = state.resources[ResourceType::Geode as usize];
-
-        state.tick_minute();
-
-        let total_num_geode = state.resources[ResourceType::Geode as usize];
-        let gain = total_num_geode - starting_geode;
-
-        let result = (if state.minutes_remaining == 0 {
-            state.maximum_geodes_seen =
-                max(state.maximum_geodes_seen, total_num_geode);
-            Some(0)
-        } else {
-            let upper_bound = self.max_geodes_upper_bound(blueprint, state);
-            if total_num_geode + upper_bound <= state.maximum_geodes_seen {
-                None
-            } else {
-                self.build_robots(blueprint, state)
-            }
-        })
-        .map(|result| result + gain);
-
-        state.untick_minute(pending);
-
-        result
-    }
-
-    fn build_robots(
-        &mut self,
-        blueprint: &Blueprint,
-        state: &mut State,
+        robot_type: ResourceType,
     ) -> Option<usize> {
-        if state.can_build(blueprint, ResourceType::Geode) {
-            let costs = blueprint.costs(ResourceType::Geode);
-            for (i, cost) in costs.iter().enumerate() {
-                state.resources[i] -= cost;
+        let mut minutes = 0;
+        for (i, &cost) in self.costs(robot_type).iter().enumerate() {
+            if state.resources[i] >= cost {
+                continue;
             }
-            state.pending_robots[ResourceType::Geode as usize] += 1;
-
-            let result = self.max_geodes_cached(blueprint, state);
-
-            state.pending_robots[ResourceType::Geode as usize] -= 1;
-            for (i, cost) in costs.iter().enumerate() {
-                state.resources[i] += cost;
+            if state.robots[i] == 0 {
+                return None;
             }
-
-            return result;
+            minutes = max(
+                minutes,
+                (cost - state.resources[i]).div_ceil(state.robots[i]),
+            );
         }
-
-        let mut result = self.max_geodes_cached(blueprint, state);
-
-        for i in (0..(NUM_RESOURCE_TYPES - 1)).rev() {
-            // Try building a robot of this type
-            let robot_type = ResourceType::from_number(i).unwrap();
-
-            if state.can_build(blueprint, robot_type) {
-                let costs = blueprint.costs(robot_type);
-                for (j, cost) in costs.iter().enumerate() {
-                    state.resources[j] -= cost;
-                }
-                state.pending_robots[i] += 1;
-
-                result = max(result, self.max_geodes_cached(blueprint, state));
-
-                state.pending_robots[i] -= 1;
-                for (j, cost) in costs.iter().enumerate() {
-                    state.resources[j] += cost;
-                }
-            }
-        }
-
-        result
+        Some(minutes + 1)
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct State {
-    minutes_remaining: usize,
-    resources: [usize; NUM_RESOURCE_TYPES],
-    robots: [usize; NUM_RESOURCE_TYPES],
-    pending_robots: [usize; NUM_RESOURCE_TYPES],
-    maximum_geodes_seen: usize,
-}
-
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-struct StateCacheEntry {
-    minutes_remaining: usize,
-    resources: [usize; NUM_RESOURCE_TYPES - 1],
-    robots: [usize; NUM_RESOURCE_TYPES],
-    pending_robots: [usize; NUM_RESOURCE_TYPES],
-}
-
-impl State {
-    fn cache_entry(&self) -> StateCacheEntry {
-        StateCacheEntry {
-            minutes_remaining: self.minutes_remaining,
-            resources: [
-                self.resources[0],
-                self.resources[1],
-                self.resources[2],
-            ],
-            robots: self.robots.clone(),
-            pending_robots: self.pending_robots.clone(),
-        }
+// Each move picks the next robot to build and waits until it is built
+impl Problem for Blueprint {
+    type State = State;
+    type Move = (ResourceType, usize);
+    type Value = usize;
+
+    fn moves(&self, state: &State) -> Vec<(ResourceType, usize)> {
+        // Try geode robots first, to find good solutions early
+        (0..NUM_RESOURCE_TYPES)
+            .rev()
+            .map(|i| ResourceType::from_number(i).unwrap())
+            .filter(|&robot_type| {
+                state.robots[robot_type as usize]
+                    < self.max_useful_robots(robot_type)
+            })
+            .filter_map(|robot_type| {
+                let minutes = self.minutes_to_build(state, robot_type)?;
+                // A robot built in the last minute never collects anything
+                (minutes < state.minutes_remaining)
+                    .then_some((robot_type, minutes))
+            })
+            .collect()
     }
 
-    fn can_build(
+    fn apply(
         &self,
-        blueprint: &Blueprint,
-        robot_type: ResourceType,
-    ) -> bool {
-        let costs = blueprint.costs(robot_type);
-        for (i, cost) in costs.iter().enumerate() {
-            if self.resources[i] < *cost {
-                return false;
-            }
+        state: &State,
+        &(robot_type, minutes): &(ResourceType, usize),
+    ) -> State {
+        let mut state = state.clone();
+        let costs = self.costs(robot_type);
+        for ((resource, robots), cost) in
+            state.resources.iter_mut().zip(state.robots).zip(costs)
+        {
+            *resource = *resource + robots * minutes - cost;
         }
-        true
+        state.robots[robot_type as usize] += 1;
+        state.minutes_remaining -= minutes;
+        state
     }
 
-    fn tick_minute(&mut self) {
-        for i in 0..NUM_RESOURCE_TYPES {
-            self.resources[i] += self.robots[i];
-            self.robots[i] += self.pending_robots[i];
-            self.pending_robots[i] = 0;
-        }
-        self.minutes_remaining -= 1;
+    // The geodes collected by the end if no more robots are built
+    fn objective(&self, state: &State) -> Option<usize> {
+        let geode = ResourceType::Geode as usize;
+        Some(
+            state.resources[geode]
+                + state.robots[geode] * state.minutes_remaining,
+        )
     }
 
-    fn untick_minute(&mut self, pending_robots: [usize; NUM_RESOURCE_TYPES]) {
-        self.minutes_remaining += 1;
-        self.pending_robots = pending_robots;
-        for i in 0..NUM_RESOURCE_TYPES {
-            self.robots[i] -= self.pending_robots[i];
-            self.resources[i] -= self.robots[i];
-        }
+    // Best case: build a geode robot every remaining minute
+    fn bound(&self, state: &State) -> usize {
+        let minutes = state.minutes_remaining;
+        self.objective(state).unwrap() + minutes * minutes.saturating_sub(1) / 2
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct State {
+    minutes_remaining: usize,
+    resources: [usize; NUM_RESOURCE_TYPES],
+    robots: [usize; NUM_RESOURCE_TYPES],
+}
+
 fn parse_costs(destination: &mut [usize], costs_text: &str) -> io::Result<()> {
     for i in 0..NUM_RESOURCE_TYPES {
         destination[i] = 0;
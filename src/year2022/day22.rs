@@ -1,6 +1,5 @@
 use std::io;
 use std::io::BufRead;
-use std::ops::Add;
 
 use crate::errors::invalid_input;
 use crate::part::Part;
@@ -21,35 +20,46 @@ enum Direction {
 }
 
 impl Direction {
-    pub fn new(ch: char) -> Option<Self> {
-        match ch {
-            'R' => Some(Self::Right),
-            'D' => Some(Self::Down),
-            'L' => Some(Self::Left),
-            'U' => Some(Self::Up),
-            _ => None,
+    const ALL: [Self; 4] = [Self::Right, Self::Down, Self::Left, Self::Up];
+
+    fn turned(self, turn: Turn) -> Self {
+        match turn {
+            Turn::Right => Self::from_number(self as usize + 1),
+            Turn::Left => Self::from_number(self as usize + 3),
         }
     }
-}
-
-impl Add for Direction {
-    type Output = Self;
 
-    fn add(self, other: Self) -> Self {
-        match (self, other) {
-            (_, Self::Up) => self,
-            (Self::Up, _) => other,
+    fn from_number(number: usize) -> Self {
+        match number % 4 {
+            0 => Self::Right,
+            1 => Self::Down,
+            2 => Self::Left,
+            _ => Self::Up,
+        }
+    }
 
-            (Self::Right, Self::Right) => Self::Down,
-            (Self::Down, Self::Right) => Self::Left,
-            (Self::Left, Self::Right) => Self::Up,
+    fn delta(self) -> (isize, isize) {
+        match self {
+            Self::Right => (0, 1),
+            Self::Down => (1, 0),
+            Self::Left => (0, -1),
+            Self::Up => (-1, 0),
+        }
+    }
+}
 
-            (Self::Right, Self::Left) => Self::Up,
-            (Self::Down, Self::Left) => Self::Right,
-            (Self::Left, Self::Left) => Self::Down,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Turn {
+    Left,
+    Right,
+}
 
-            // TODO
-            (_, Self::Down) => unimplemented!(),
+impl Turn {
+    pub fn new(ch: char) -> Option<Self> {
+        match ch {
+            'L' => Some(Self::Left),
+            'R' => Some(Self::Right),
+            _ => None,
         }
     }
 }
@@ -57,7 +67,7 @@ impl Add for Direction {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Instruction {
     Move(usize),
-    Turn(Direction),
+    Turn(Turn),
 }
 
 impl Tile {
@@ -141,258 +151,248 @@ impl Map {
         self.tiles.push(tiles);
     }
 
-    fn try_move_to(&mut self, candidate_position: Position) {
-        let tile = self.tiles[candidate_position.row][candidate_position.col];
-        if tile == Tile::Open {
-            self.my_position = Some(candidate_position);
+    fn tile_at(&self, row: isize, col: isize) -> Tile {
+        if row < 0 || col < 0 {
+            return Tile::Nothing;
         }
+        self.tiles
+            .get(row as usize)
+            .and_then(|tiles| tiles.get(col as usize))
+            .copied()
+            .unwrap_or(Tile::Nothing)
     }
 
-    fn wrap_up(&mut self) {
-        // TODO
-        let position = self.my_position.as_mut().expect("Must have position");
+    // Where moving off the edge of the map from `position` leads on a flat
+    // map: the far side of the same row or column
+    fn wrap_flat(&self, position: Position) -> Position {
+        let (delta_row, delta_col) = position.direction.delta();
+        let (mut row, mut col) = (position.row as isize, position.col as isize);
+        while self.tile_at(row - delta_row, col - delta_col) != Tile::Nothing {
+            row -= delta_row;
+            col -= delta_col;
+        }
+        Position {
+            row: row as usize,
+            col: col as usize,
+            ..position
+        }
+    }
 
-        let candidate_position = if position.col < 50 {
-            // Wrap to left of C
-            Position {
-                row: 50 + position.col,
-                col: 50,
-                direction: Direction::Right,
-            }
-        } else if position.col < 100 {
-            // Wrap to left of F
-            Position {
-                row: 100 + position.col,
-                col: 0,
-                direction: Direction::Right,
+    fn move_one(&mut self, cube: Option<&Cube>) {
+        let position = self.my_position.expect("Must have position");
+        let (delta_row, delta_col) = position.direction.delta();
+        let (row, col) = (
+            position.row as isize + delta_row,
+            position.col as isize + delta_col,
+        );
+
+        let candidate_position = if self.tile_at(row, col) == Tile::Nothing {
+            match cube {
+                Some(cube) => cube.wrap(position),
+                None => self.wrap_flat(position),
             }
         } else {
-            // Wrap to bottom of F
             Position {
-                row: 199,
-                col: position.col - 100,
-                direction: Direction::Up,
+                row: row as usize,
+                col: col as usize,
+                ..position
             }
         };
 
-        self.try_move_to(candidate_position);
+        let tile = self.tiles[candidate_position.row][candidate_position.col];
+        if tile == Tile::Open {
+            self.my_position = Some(candidate_position);
+        }
     }
 
-    fn wrap_down(&mut self) {
-        // TODO
+    pub fn perform(&mut self, instruction: Instruction, cube: Option<&Cube>) {
         let position = self.my_position.as_mut().expect("Must have position");
 
-        let candidate_position = if position.col < 50 {
-            // Wrap to top of B
-            Position {
-                row: 0,
-                col: 100 + position.col,
-                direction: Direction::Down,
-            }
-        } else if position.col < 100 {
-            // Wrap to right of F
-            Position {
-                row: 100 + position.col,
-                col: 49,
-                direction: Direction::Left,
+        match instruction {
+            Instruction::Turn(turn) => {
+                position.direction = position.direction.turned(turn);
             }
-        } else {
-            // Wrap to right of C
-            Position {
-                row: position.col - 50,
-                col: 99,
-                direction: Direction::Left,
+            Instruction::Move(num_tiles) => {
+                for _ in 0..num_tiles {
+                    self.move_one(cube);
+                }
             }
-        };
-
-        self.try_move_to(candidate_position);
+        }
     }
+}
 
-    fn wrap_left(&mut self) {
-        // TODO
-        let position = self.my_position.as_mut().expect("Must have position");
+type Vector = [isize; 3];
 
-        let candidate_position = if position.row < 50 {
-            // Wrap to left of D
-            Position {
-                row: 149 - position.row,
-                col: 0,
-                direction: Direction::Right,
-            }
-        } else if position.row < 100 {
-            // Wrap to top of D
-            Position {
-                row: 100,
-                col: position.row - 50,
-                direction: Direction::Down,
-            }
-        } else if position.row < 150 {
-            // Wrap to left of A
-            Position {
-                row: 149 - position.row,
-                col: 50,
-                direction: Direction::Right,
-            }
-        } else {
-            // Wrap to top of A
-            Position {
-                row: 0,
-                col: position.row - 100,
-                direction: Direction::Down,
-            }
-        };
+fn neg(vector: Vector) -> Vector {
+    vector.map(|value| -value)
+}
 
-        self.try_move_to(candidate_position);
-    }
+fn dot(a: Vector, b: Vector) -> isize {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
 
-    fn wrap_right(&mut self) {
-        // TODO
-        let position = self.my_position.as_mut().expect("Must have position");
+// One face of the cube, with the directions that its normal, columns and rows
+// point in once the net is folded up
+#[derive(Clone, Copy, Debug)]
+struct Face {
+    row: usize,
+    col: usize,
+    normal: Vector,
+    right: Vector,
+    down: Vector,
+}
 
-        let candidate_position = if position.row < 50 {
-            // Wrap to right of E
-            Position {
-                row: 149 - position.row,
-                col: 99,
-                direction: Direction::Left,
-            }
-        } else if position.row < 100 {
-            // Wrap to bottom of B
-            Position {
-                row: 49,
-                col: 50 + position.row,
-                direction: Direction::Up,
-            }
-        } else if position.row < 150 {
-            // Wrap to right of B
-            Position {
-                row: 149 - position.row,
-                col: 149,
-                direction: Direction::Left,
-            }
-        } else {
-            // Wrap to bottom of E
-            Position {
-                row: 149,
-                col: position.row - 100,
-                direction: Direction::Up,
-            }
-        };
+impl Face {
+    fn direction_vector(&self, direction: Direction) -> Vector {
+        match direction {
+            Direction::Right => self.right,
+            Direction::Down => self.down,
+            Direction::Left => neg(self.right),
+            Direction::Up => neg(self.down),
+        }
+    }
 
-        self.try_move_to(candidate_position);
+    // The face across the edge in `direction` in the net, once it's folded
+    // up to meet this one
+    fn folded(&self, direction: Direction, face_size: usize) -> Self {
+        let (delta_row, delta_col) = direction.delta();
+        let step = face_size as isize;
+        let (normal, right, down) = match direction {
+            Direction::Right => (self.right, neg(self.normal), self.down),
+            Direction::Left => (neg(self.right), self.normal, self.down),
+            Direction::Down => (self.down, self.right, neg(self.normal)),
+            Direction::Up => (neg(self.down), self.right, self.normal),
+        };
+        Self {
+            row: self.row.wrapping_add_signed(delta_row * step),
+            col: self.col.wrapping_add_signed(delta_col * step),
+            normal,
+            right,
+            down,
+        }
     }
+}
 
-    fn move_one(&mut self) {
-        // TODO
-        let position = self.my_position.as_mut().expect("Must have position");
+// The map folded up into a cube centred on the origin, with side length twice
+// the face size, so that the centre of every tile has integer coordinates
+struct Cube {
+    face_size: usize,
+    faces: Vec<Face>,
+}
 
-        match position.direction {
-            // TODO dup
-            Direction::Left => {
-                if position.col == 0 {
-                    self.wrap_left();
-                } else {
-                    let tile = self.tiles[position.row][position.col - 1];
-                    match tile {
-                        Tile::Wall => {
-                            // Do nothing
-                        }
-                        Tile::Nothing => {
-                            self.wrap_left();
-                        }
-                        Tile::Open => {
-                            position.col -= 1;
-                        }
-                    }
-                }
-            }
-            Direction::Right => {
-                let row = &self.tiles[position.row];
-                if position.col + 1 >= row.len() {
-                    self.wrap_right();
-                } else {
-                    let tile = self.tiles[position.row][position.col + 1];
-                    match tile {
-                        Tile::Wall => {
-                            // Do nothing
-                        }
-                        Tile::Nothing => {
-                            self.wrap_right();
-                        }
-                        Tile::Open => {
-                            position.col += 1;
-                        }
-                    }
-                }
-            }
-            Direction::Up => {
-                if position.row == 0 {
-                    self.wrap_up();
-                } else {
-                    let row = &self.tiles[position.row - 1];
-                    if position.col >= row.len() {
-                        self.wrap_up();
-                    } else {
-                        let tile = self.tiles[position.row - 1][position.col];
-                        match tile {
-                            Tile::Wall => {
-                                // Do nothing
-                            }
-                            Tile::Nothing => {
-                                self.wrap_up();
-                            }
-                            Tile::Open => {
-                                position.row -= 1;
-                            }
-                        }
-                    }
-                }
-            }
-            Direction::Down => {
-                if position.row + 1 >= self.tiles.len() {
-                    self.wrap_down();
-                } else {
-                    let row = &self.tiles[position.row + 1];
-                    if position.col >= row.len() {
-                        self.wrap_down();
-                    } else {
-                        let tile = self.tiles[position.row + 1][position.col];
-                        match tile {
-                            Tile::Wall => {
-                                // Do nothing
-                            }
-                            Tile::Nothing => {
-                                self.wrap_down();
-                            }
-                            Tile::Open => {
-                                position.row += 1;
-                            }
-                        }
-                    }
+impl Cube {
+    fn new(map: &Map) -> io::Result<Self> {
+        let num_tiles = map
+            .tiles
+            .iter()
+            .flatten()
+            .filter(|&&tile| tile != Tile::Nothing)
+            .count();
+        let face_size = (num_tiles / 6).isqrt();
+        if face_size == 0 || 6 * face_size * face_size != num_tiles {
+            return Err(invalid_input("Map doesn't have six square faces"));
+        }
+
+        let has_face = |face: &Face| {
+            map.tile_at(face.row as isize, face.col as isize) != Tile::Nothing
+        };
+        let first_face = (0..map.tiles.len())
+            .step_by(face_size)
+            .flat_map(|row| {
+                (0..map.tiles[row].len())
+                    .step_by(face_size)
+                    .map(move |col| (row, col))
+            })
+            .map(|(row, col)| Face {
+                row,
+                col,
+                normal: [0, 0, 1],
+                right: [1, 0, 0],
+                down: [0, 1, 0],
+            })
+            .find(has_face)
+            .ok_or_else(|| invalid_input("Empty map"))?;
+
+        // Fold each face up from a neighbour in the net
+        let mut faces = vec![first_face];
+        let mut index = 0;
+        while index < faces.len() {
+            for direction in Direction::ALL {
+                let face = faces[index].folded(direction, face_size);
+                let is_new = faces.iter().all(|other| {
+                    (other.row, other.col) != (face.row, face.col)
+                });
+                if is_new && has_face(&face) {
+                    faces.push(face);
                 }
             }
+            index += 1;
+        }
+
+        let mut normals =
+            faces.iter().map(|face| face.normal).collect::<Vec<_>>();
+        normals.sort_unstable();
+        normals.dedup();
+        if faces.len() != 6 || normals.len() != 6 {
+            return Err(invalid_input("Map doesn't fold into a cube"));
         }
+
+        Ok(Self { face_size, faces })
     }
 
-    pub fn perform(&mut self, instruction: Instruction) {
-        // TODO
-        let position = self.my_position.as_mut().expect("Must have position");
+    fn face_containing(&self, position: Position) -> &Face {
+        self.faces
+            .iter()
+            .find(|face| {
+                (face.row..face.row + self.face_size).contains(&position.row)
+                    && (face.col..face.col + self.face_size)
+                        .contains(&position.col)
+            })
+            .expect("Position should be on a face")
+    }
 
-        match instruction {
-            Instruction::Turn(turn_direction) => {
-                position.direction = position.direction + turn_direction;
-            }
-            Instruction::Move(num_tiles) => {
-                for _ in 0..num_tiles {
-                    self.move_one();
-                }
-            }
+    // Where moving off the edge of a face from `position` leads
+    fn wrap(&self, position: Position) -> Position {
+        let size = self.face_size as isize;
+        let face = self.face_containing(position);
+        let edge = face.direction_vector(position.direction);
+        let new_face = self
+            .faces
+            .iter()
+            .find(|other| other.normal == edge)
+            .unwrap();
+
+        // Stepping over the edge moves out along the direction of travel and
+        // down towards the new face
+        let (row, col) = (
+            (position.row - face.row) as isize,
+            (position.col - face.col) as isize,
+        );
+        let point: Vector = std::array::from_fn(|i| {
+            size * face.normal[i]
+                + (2 * col + 1 - size) * face.right[i]
+                + (2 * row + 1 - size) * face.down[i]
+                + edge[i]
+                - face.normal[i]
+        });
+
+        let to_tile = |vector| ((dot(point, vector) + size - 1) / 2) as usize;
+        let direction = Direction::ALL
+            .into_iter()
+            .find(|&direction| {
+                new_face.direction_vector(direction) == neg(face.normal)
+            })
+            .unwrap();
+        Position {
+            row: new_face.row + to_tile(new_face.down),
+            col: new_face.col + to_tile(new_face.right),
+            direction,
         }
     }
 }
 
 pub fn run<R: io::Read>(
-    _part: Part,
+    part: Part,
     reader: io::BufReader<R>,
 ) -> io::Result<()> {
     let mut map = Map::new();
@@ -439,11 +439,10 @@ pub fn run<R: io::Read>(
                         }
                         2 => {
                             for ch in string.chars() {
-                                let direction =
-                                    Direction::new(ch).ok_or_else(|| {
-                                        invalid_input("Unexpected direction")
-                                    })?;
-                                instructions.push(Instruction::Turn(direction));
+                                let turn = Turn::new(ch).ok_or_else(|| {
+                                    invalid_input("Unexpected direction")
+                                })?;
+                                instructions.push(Instruction::Turn(turn));
                             }
                         }
                         _ => unreachable!(),
@@ -463,11 +462,10 @@ pub fn run<R: io::Read>(
                 }
                 2 => {
                     for ch in string.chars() {
-                        let direction =
-                            Direction::new(ch).ok_or_else(|| {
-                                invalid_input("Unexpected direction")
-                            })?;
-                        instructions.push(Instruction::Turn(direction));
+                        let turn = Turn::new(ch).ok_or_else(|| {
+                            invalid_input("Unexpected direction")
+                        })?;
+                        instructions.push(Instruction::Turn(turn));
                     }
                 }
                 _ => unreachable!(),
@@ -475,13 +473,13 @@ pub fn run<R: io::Read>(
         }
     }
 
-    // TODO
+    let cube = match part {
+        Part::Part1 => None,
+        Part::Part2 => Some(Cube::new(&map)?),
+    };
+
     for instruction in instructions {
-        //map.print();
-        map.perform(instruction);
-        //std::thread::sleep(std::time::Duration::from_millis(1000));
-        //println!("After {:?}     \tat {:?}", instruction,
-        // map.my_position.unwrap());
+        map.perform(instruction, cube.as_ref());
     }
 
     //map.print();
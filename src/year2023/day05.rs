@@ -1,30 +1,17 @@
-use std::cmp::{max, min};
-use std::collections::{BTreeSet, HashSet};
 use std::io;
 use std::ops::Range;
 
 use crate::errors::invalid_input;
+use crate::interval_set::IntervalSet;
 use crate::parse::{lines, paragraphs, parse_words};
 use crate::part::Part;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
 struct MappingRange {
     source_start: i64,
     destination_start: i64,
     length: i64,
 }
 
-#[derive(Debug)]
-struct TryMapResult {
-    mapped: Range<i64>,
-    left_before: Range<i64>,
-    left_after: Range<i64>,
-}
-
-fn intersect<T: Ord>(r1: Range<T>, r2: Range<T>) -> Range<T> {
-    max(r1.start, r2.start)..min(r1.end, r2.end)
-}
-
 impl MappingRange {
     fn from_line(line: &str) -> io::Result<Self> {
         let [destination_start, source_start, length] =
@@ -40,64 +27,29 @@ impl MappingRange {
         })
     }
 
-    fn try_map(&self, source_range: Range<i64>) -> TryMapResult {
-        let left_before = intersect(
-            source_range.clone(),
-            source_range.start..self.source_start,
-        );
-        let left_after = intersect(
-            source_range.clone(),
-            (self.source_start + self.length)..source_range.end,
-        );
-        let overlap = intersect(
-            source_range.clone(),
+    fn shift(&self) -> (Range<i64>, i64) {
+        (
             self.source_start..(self.source_start + self.length),
-        );
-        let mapped = (overlap.start - self.source_start
-            + self.destination_start)
-            ..(overlap.end - self.source_start + self.destination_start);
-        TryMapResult {
-            mapped,
-            left_before,
-            left_after,
-        }
+            self.destination_start - self.source_start,
+        )
     }
 }
 
 struct Mapping {
-    ranges: BTreeSet<MappingRange>,
+    shifts: Vec<(Range<i64>, i64)>,
 }
 
 impl Mapping {
     fn new() -> Self {
-        Self {
-            ranges: BTreeSet::new(),
-        }
+        Self { shifts: Vec::new() }
     }
 
     fn add_range(&mut self, range: MappingRange) {
-        self.ranges.insert(range);
+        self.shifts.push(range.shift());
     }
 
-    fn apply(&self, source: Range<i64>) -> HashSet<Range<i64>> {
-        let mut remaining = source.clone(); // TODO
-        let mut result = HashSet::<Range<i64>>::new();
-        for range in &self.ranges {
-            let map_result = range.try_map(remaining);
-            //println!("{:?}", map_result);
-            if !map_result.left_before.is_empty() {
-                result.insert(map_result.left_before);
-            }
-            if !map_result.mapped.is_empty() {
-                result.insert(map_result.mapped);
-            }
-            if map_result.left_after.is_empty() {
-                break;
-            }
-            remaining = map_result.left_after;
-        }
-
-        result
+    fn apply(&self, source: &IntervalSet<i64>) -> IntervalSet<i64> {
+        source.shift_pieces(&self.shifts)
     }
 }
 
@@ -145,18 +97,12 @@ impl Input {
 }
 
 fn get_locations(
-    seeds: HashSet<Range<i64>>,
-    mappings: &Vec<Mapping>,
-) -> HashSet<Range<i64>> {
-    let mut result = seeds;
-    for mapping in mappings {
-        let mut next_set = HashSet::<Range<i64>>::new();
-        for num in result {
-            next_set.extend(mapping.apply(num));
-        }
-        result = next_set;
-    }
-    result
+    seeds: IntervalSet<i64>,
+    mappings: &[Mapping],
+) -> IntervalSet<i64> {
+    mappings
+        .iter()
+        .fold(seeds, |result, mapping| mapping.apply(&result))
 }
 
 fn part1<R: io::Read>(reader: io::BufReader<R>) -> io::Result<()> {
@@ -166,13 +112,11 @@ fn part1<R: io::Read>(reader: io::BufReader<R>) -> io::Result<()> {
         .seeds
         .into_iter()
         .map(|seed| seed..(seed + 1))
-        .collect::<HashSet<_>>();
+        .collect::<IntervalSet<_>>();
 
     let locations = get_locations(seeds, &input.mappings);
 
     let min_location = locations
-        .into_iter()
-        .map(|r| r.start)
         .min()
         .ok_or_else(|| invalid_input("No locations"))?;
 
@@ -188,16 +132,16 @@ fn part2<R: io::Read>(reader: io::BufReader<R>) -> io::Result<()> {
         return Err(invalid_input("Invalid seed ranges"));
     }
 
-    let mut seeds = HashSet::<Range<i64>>::new();
-    for [start, len] in input.seeds.into_iter().array_chunks::<2>() {
-        seeds.insert(start..(start + len));
-    }
+    let seeds = input
+        .seeds
+        .into_iter()
+        .array_chunks::<2>()
+        .map(|[start, len]| start..(start + len))
+        .collect::<IntervalSet<_>>();
 
     let locations = get_locations(seeds, &input.mappings);
 
     let min_location = locations
-        .iter()
-        .map(|r| r.start)
         .min()
         .ok_or_else(|| invalid_input("No locations"))?;
 
@@ -1,8 +1,9 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
 use std::io;
 
+use crate::cycle::{Cycle, find_cycle};
 use crate::errors::invalid_input;
+use crate::graph::Interner;
+use crate::math::chinese_remainder;
 use crate::parse::{lines, paragraphs};
 use crate::part::Part;
 
@@ -22,18 +23,16 @@ impl Step {
     }
 }
 
-struct Paths {
-    left_path: String,
-    right_path: String,
-}
-
 struct Map {
-    paths_by_source: HashMap<String, Paths>,
+    names: Interner,
+    // The (left, right) destinations from each location
+    paths: Vec<Option<(usize, usize)>>,
 }
 
 impl Map {
-    fn from_lines(lines: &Vec<String>) -> io::Result<Self> {
-        let mut paths_by_source = HashMap::<String, Paths>::new();
+    fn from_lines(lines: &[String]) -> io::Result<Self> {
+        let mut names = Interner::new();
+        let mut paths = Vec::<Option<(usize, usize)>>::new();
         for line in lines {
             let [source, _, left, right] =
                 line.split_whitespace().collect::<Vec<_>>()[..]
@@ -50,163 +49,128 @@ impl Map {
                 .strip_suffix(")")
                 .ok_or_else(|| invalid_input("Invalid right punctuation"))?;
 
-            if paths_by_source.contains_key(source) {
+            let source = names.intern(source);
+            let left = names.intern(left);
+            let right = names.intern(right);
+            paths.resize(names.len(), None);
+            if paths[source].is_some() {
                 return Err(invalid_input("Duplicate source"));
             }
-            paths_by_source.insert(
-                source.to_owned(),
-                Paths {
-                    left_path: left.to_owned(),
-                    right_path: right.to_owned(),
-                },
-            );
+            paths[source] = Some((left, right));
         }
 
-        Ok(Self { paths_by_source })
-    }
+        if paths.iter().any(Option::is_none) {
+            return Err(invalid_input("Path to unknown location"));
+        }
 
-    fn contains_source(&self, source: &str) -> bool {
-        self.paths_by_source.contains_key(source)
+        Ok(Self { names, paths })
     }
 
-    fn travel(&self, start: &str, step: Step) -> &str {
-        assert!(self.contains_source(start));
-        let paths = self.paths_by_source.get(start).unwrap();
+    fn travel(&self, location: usize, step: Step) -> usize {
+        let (left, right) = self.paths[location].unwrap();
         match step {
-            Step::Left => &paths.left_path,
-            Step::Right => &paths.right_path,
+            Step::Left => left,
+            Step::Right => right,
         }
     }
-
-    fn sources(&self) -> impl Iterator<Item = &str> {
-        self.paths_by_source.keys().map(|key| key.as_ref())
-    }
 }
 
-// This traverser-based algorithm works generically. It handles multiple ends
-// in a cycle, starting points that are not part of the cycle, etc. However, it
-// is much smaller than an LCM-based solution, which would work for contrived
-// inputs such as my AOC input.
-struct Traverser<'a, const IS_PART_2: bool> {
-    map: Map,
-    steps: Vec<Step>,
-    cache: RefCell<HashMap<(usize, &'a str), (usize, &'a str)>>,
+// The numbers of steps after which a ghost is at an end location. Once the
+// ghost is in a cycle, these repeat every cycle.
+struct EndTimes {
+    before_cycle: Vec<usize>,
+    in_cycle: Vec<usize>,
+    cycle: Cycle,
 }
 
-const PART_1_START_LOCATION: &str = "AAA";
-
-impl<'a, const IS_PART_2: bool> Traverser<'a, IS_PART_2> {
-    fn new(map: Map, steps: Vec<Step>) -> io::Result<Self> {
-        if steps.is_empty() {
-            return Err(invalid_input("No steps"));
-        }
-
-        if !IS_PART_2 && !map.contains_source(PART_1_START_LOCATION) {
-            return Err(invalid_input("No start location"));
+impl EndTimes {
+    fn new(map: &Map, steps: &[Step], start: usize, is_end: &[bool]) -> Self {
+        // The state is the current location and the number of steps taken
+        let mut end_times = Vec::<usize>::new();
+        let cycle = find_cycle(
+            &mut (start, 0usize),
+            |(location, time)| {
+                *location = map.travel(*location, steps[*time % steps.len()]);
+                *time += 1;
+            },
+            |&(location, time)| (location, time % steps.len()),
+            |&(location, time)| {
+                if time != 0 && is_end[location] {
+                    end_times.push(time);
+                }
+            },
+        );
+
+        let (before_cycle, in_cycle) = end_times
+            .into_iter()
+            .filter(|&time| time < cycle.start + cycle.length)
+            .partition(|&time| time < cycle.start);
+        Self {
+            before_cycle,
+            in_cycle,
+            cycle,
         }
-
-        Ok(Self {
-            map,
-            steps,
-            cache: RefCell::new(HashMap::new()),
-        })
     }
 
-    fn is_end(location: &str) -> bool {
-        if IS_PART_2 {
-            location.ends_with("Z")
+    fn is_end_time(&self, time: usize) -> bool {
+        if time < self.cycle.start {
+            self.before_cycle.contains(&time)
         } else {
-            location == "ZZZ"
+            let time_in_cycle = self.cycle.start
+                + (time - self.cycle.start) % self.cycle.length;
+            self.in_cycle.contains(&time_in_cycle)
         }
     }
+}
 
-    fn start_locations(&self) -> Vec<&str> {
-        if IS_PART_2 {
-            self.map
-                .sources()
-                .filter(|source| source.ends_with("A"))
-                .collect()
-        } else {
-            vec![PART_1_START_LOCATION]
-        }
+fn earliest_common_end_time(ghosts: &[EndTimes]) -> Option<usize> {
+    if ghosts.is_empty() {
+        return Some(0);
     }
 
-    fn distance_to_next_end(
-        &'a self,
-        current_offset: usize,
-        current_location: &'a str,
-    ) -> (usize, &'a str) {
-        let cache_key = (current_offset % self.steps.len(), current_location);
-        {
-            if let Some(result) = self.cache.borrow().get(&cache_key) {
-                return *result;
-            }
-        }
-
-        // We don't cache results that aren't explicitly asked for, because it
-        // won't help much and just takes extra memory.
-        let mut distance: usize = 0;
-        let mut location = current_location;
-
-        loop {
-            location = self.map.travel(
-                location,
-                self.steps[(current_offset + distance) % self.steps.len()],
-            );
-            distance += 1;
-
-            if Self::is_end(location) {
-                break;
-            }
-        }
-
-        self.cache
-            .borrow_mut()
-            .insert(cache_key, (distance, location));
-        (distance, location)
+    // Either all ghosts are at an end before one of them enters its cycle...
+    let before_cycles = ghosts
+        .iter()
+        .flat_map(|ghost| ghost.before_cycle.iter().copied())
+        .filter(|&time| ghosts.iter().all(|ghost| ghost.is_end_time(time)))
+        .min();
+    if before_cycles.is_some() {
+        return before_cycles;
     }
 
-    fn distance_until_all_at_end(&'a self) -> usize {
-        let mut locations = self.start_locations();
-
-        if locations.is_empty() {
-            return 0;
+    // ...or they're all in their cycles, at one of their end times in the
+    // cycle. Try every combination of these.
+    let min_time = ghosts.iter().map(|ghost| ghost.cycle.start).max()? as i128;
+    let mut best: Option<i128> = None;
+    let mut combination = vec![0usize; ghosts.len()];
+    loop {
+        let mut congruences = Vec::<(i128, i128)>::new();
+        for (ghost, &index) in ghosts.iter().zip(combination.iter()) {
+            let &time = ghost.in_cycle.get(index)?;
+            congruences.push((time as i128, ghost.cycle.length as i128));
         }
-
-        let mut offsets = Vec::<usize>::new();
-        offsets.reserve(locations.len());
-        for location in locations.iter_mut() {
-            let (distance, next_location) =
-                self.distance_to_next_end(0, *location);
-            *location = next_location;
-            offsets.push(distance);
+        if let Some((residue, modulus)) = chinese_remainder(&congruences) {
+            let mut time = residue;
+            if time < min_time {
+                time += (min_time - time + modulus - 1) / modulus * modulus;
+            }
+            if best.is_none_or(|best| time < best) {
+                best = Some(time);
+            }
         }
 
+        // Move on to the next combination
+        let mut i = 0;
         loop {
-            let mut min_offset = usize::MAX;
-            let mut min_offset_idx: usize = 0;
-            let mut max_offset = usize::MIN;
-            for (i, offset) in offsets.iter().enumerate() {
-                let offset = *offset;
-                if offset < min_offset {
-                    min_offset_idx = i;
-                    min_offset = offset;
-                }
-                if offset > max_offset {
-                    max_offset = offset;
-                }
+            if i == ghosts.len() {
+                return best.map(|time| time as usize);
             }
-
-            if min_offset == max_offset {
-                return min_offset;
+            combination[i] += 1;
+            if combination[i] < ghosts[i].in_cycle.len() {
+                break;
             }
-
-            let (distance, next_location) = self.distance_to_next_end(
-                offsets[min_offset_idx],
-                locations[min_offset_idx],
-            );
-            offsets[min_offset_idx] += distance;
-            locations[min_offset_idx] = next_location;
+            combination[i] = 0;
+            i += 1;
         }
     }
 }
@@ -228,16 +192,41 @@ pub fn run<R: io::Read>(
         .chars()
         .map(Step::from_char)
         .collect::<io::Result<Vec<_>>>()?;
+    if steps.is_empty() {
+        return Err(invalid_input("No steps"));
+    }
 
     let map = Map::from_lines(map)?;
 
-    let result = match part {
+    let locations = 0..map.paths.len();
+    let (starts, is_end): (Vec<_>, Vec<_>) = match part {
         Part::Part1 => {
-            Traverser::<false>::new(map, steps)?.distance_until_all_at_end()
-        }
-        Part::Part2 => {
-            Traverser::<true>::new(map, steps)?.distance_until_all_at_end()
+            let Some(start) = map.names.id("AAA") else {
+                return Err(invalid_input("No start location"));
+            };
+            (
+                vec![start],
+                locations.map(|id| map.names.name(id) == "ZZZ").collect(),
+            )
         }
+        Part::Part2 => (
+            locations
+                .clone()
+                .filter(|&id| map.names.name(id).ends_with('A'))
+                .collect(),
+            locations
+                .map(|id| map.names.name(id).ends_with('Z'))
+                .collect(),
+        ),
+    };
+
+    let ghosts = starts
+        .into_iter()
+        .map(|start| EndTimes::new(&map, &steps, start, &is_end))
+        .collect::<Vec<_>>();
+
+    let Some(result) = earliest_common_end_time(&ghosts) else {
+        return Err(invalid_input("The end is never reached"));
     };
 
     println!("{result}");
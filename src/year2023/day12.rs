@@ -1,7 +1,7 @@
-use std::collections::HashMap;
 use std::io;
 
 use crate::errors::invalid_input;
+use crate::memo::Memo;
 use crate::parse::{lines, parse_all};
 use crate::part::Part;
 
@@ -30,7 +30,7 @@ struct State {
     next_group_index: usize,
 }
 
-type Cache = HashMap<State, usize>;
+type Cache = Memo<State, usize>;
 
 struct RowOfSprings {
     springs: Vec<Spring>,
@@ -110,13 +110,9 @@ impl RowOfSprings {
         state: State,
         cache: &mut Cache,
     ) -> usize {
-        if let Some(result) = cache.get(&state) {
-            *result
-        } else {
-            let result = self.possible_arrangements_helper(state, cache);
-            cache.insert(state, result);
-            result
-        }
+        cache.get_or_compute(state, |cache| {
+            self.possible_arrangements_helper(state, cache)
+        })
     }
 
     fn possible_arrangements_helper(
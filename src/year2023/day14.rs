@@ -1,6 +1,6 @@
-use std::collections::HashMap;
 use std::io;
 
+use crate::cycle::advance;
 use crate::errors::invalid_input;
 use crate::parse::lines;
 use crate::part::Part;
@@ -139,31 +139,11 @@ impl Platform {
     }
 
     fn tilt_n_cycles(&mut self, num_cycles: usize) {
-        // The idea behind this function is that eventually, we will enter some
-        // state were n cycles has no effect. I call this a "cycle of cycles."
-        // Once we discover n, we can skip forward in chunks of n knowing that
-        // those chunks of cycles have no effect.
-
-        // This map could get pretty big pretty quickly, but for the inputs I've
-        // seen, it works fine.
-        let mut seen_maps = HashMap::<Vec<Vec<Tile>>, usize>::new();
-        for i in 0..num_cycles {
-            if let Some(cycle_of_cycles_start_index) = seen_maps.get(&self.map)
-            {
-                // We've found a cycle of cycles!
-                let cycle_of_cycles_length = i - cycle_of_cycles_start_index;
-                let cycles_remaining_before_skip = num_cycles - i;
-                let cycles_remaining_after_skip =
-                    cycles_remaining_before_skip % cycle_of_cycles_length;
-                for _ in 0..cycles_remaining_after_skip {
-                    self.tilt_cycle();
-                }
-                return;
-            } else {
-                seen_maps.insert(self.map.clone(), i);
-            }
-            self.tilt_cycle();
-        }
+        // Eventually the platform returns to an earlier layout, after which
+        // whole cycles of cycles can be skipped.
+        advance(self, num_cycles, Self::tilt_cycle, |platform| {
+            platform.map.clone()
+        });
     }
 
     fn total_load(&self) -> usize {
@@ -2,57 +2,13 @@ use std::collections::{HashMap, VecDeque};
 use std::io;
 
 use crate::errors::invalid_input;
+use crate::interval_set::IntervalSet;
 use crate::parse::{lines, paragraphs};
 use crate::part::Part;
 
 type Category = char;
 type Value = i64;
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct Range {
-    start: Value,
-    end: Value, // exclusive
-}
-
-impl Range {
-    fn size(&self) -> Value {
-        self.end - self.start
-    }
-
-    fn split_at(&self, value: Value) -> (Self, Self) {
-        if value > self.start {
-            if value >= self.end {
-                (
-                    *self,
-                    Self {
-                        start: value,
-                        end: value,
-                    },
-                )
-            } else {
-                (
-                    Self {
-                        start: self.start,
-                        end: value,
-                    },
-                    Self {
-                        start: value,
-                        end: self.end,
-                    },
-                )
-            }
-        } else {
-            (
-                Self {
-                    start: value,
-                    end: value,
-                },
-                *self,
-            )
-        }
-    }
-}
-
 // It would be faster to use an array/struct instead of a hashmap, but a hashmap
 // is fast enough.
 struct MachinePart {
@@ -95,39 +51,39 @@ impl MachinePart {
 
 #[derive(Clone)]
 struct MachinePartSet {
-    ratings: HashMap<Category, Range>,
+    ratings: HashMap<Category, IntervalSet<Value>>,
 }
 
 impl MachinePartSet {
     fn new() -> Self {
-        let starting_range = Range {
-            start: 1,
-            end: 4000 + 1,
-        };
+        let starting_range = IntervalSet::from(1..(4000 + 1));
         Self {
             ratings: HashMap::from([
-                ('x', starting_range),
-                ('m', starting_range),
-                ('a', starting_range),
+                ('x', starting_range.clone()),
+                ('m', starting_range.clone()),
+                ('a', starting_range.clone()),
                 ('s', starting_range),
             ]),
         }
     }
 
     fn empty() -> Self {
-        let empty_range = Range { start: 1, end: 1 };
+        let empty_range = IntervalSet::new();
         Self {
             ratings: HashMap::from([
-                ('x', empty_range),
-                ('m', empty_range),
-                ('a', empty_range),
+                ('x', empty_range.clone()),
+                ('m', empty_range.clone()),
+                ('a', empty_range.clone()),
                 ('s', empty_range),
             ]),
         }
     }
 
     fn size(&self) -> Value {
-        self.ratings.values().map(Range::size).product()
+        self.ratings
+            .values()
+            .map(IntervalSet::total_len::<Value>)
+            .product()
     }
 }
 
@@ -212,18 +168,12 @@ impl Condition {
             return (MachinePartSet::empty(), set);
         };
 
-        let (matching, not_matching) = match self.operator {
-            Operator::LessThan => {
-                let (matching, not_matching) =
-                    existing_rating.split_at(self.constant);
-                (matching, not_matching)
-            }
-            Operator::GreaterThan => {
-                let (not_matching, matching) =
-                    existing_rating.split_at(self.constant + 1);
-                (matching, not_matching)
-            }
-        };
+        let matching_values = IntervalSet::from(match self.operator {
+            Operator::LessThan => Value::MIN..self.constant,
+            Operator::GreaterThan => (self.constant + 1)..Value::MAX,
+        });
+        let matching = existing_rating.intersection(&matching_values);
+        let not_matching = existing_rating.difference(&matching_values);
         let mut new_set = set.clone();
         set.ratings.insert(self.category, matching);
         new_set.ratings.insert(self.category, not_matching);
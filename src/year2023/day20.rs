@@ -2,9 +2,19 @@ use std::collections::{HashMap, VecDeque};
 use std::io;
 
 use crate::errors::invalid_input;
+use crate::math::chinese_remainder;
 use crate::parse::lines;
 use crate::part::Part;
 
+const SAND_MOVER: &str = "rx";
+
+const BUTTON_PRESSES: usize = 1000;
+
+// How many high pulses each input of the conjunction feeding rx has to send
+// before its cycle is trusted, and how long to wait for them
+const CYCLES_TO_CHECK: usize = 3;
+const MAX_BUTTON_PRESSES: usize = 1_000_000;
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 enum Pulse {
     Low,
@@ -19,37 +29,6 @@ enum ModuleState {
     SandMover(bool),
 }
 
-impl ModuleState {
-    fn _debug_compare(&self, key: &str, new_state: &Self) {
-        if self != new_state {
-            match self {
-                Self::FlipFlop(was_on) => {
-                    let Self::FlipFlop(is_on) = new_state else {
-                        panic!("Invalid compare");
-                    };
-                    println!("{key} flipped from {was_on} to {is_on}");
-                }
-                Self::Conjunction(old_inputs) => {
-                    let Self::Conjunction(new_inputs) = new_state else {
-                        panic!("Invalid compare");
-                    };
-                    // TODO should I print more often here??
-                    let old_was_low =
-                        old_inputs.values().all(|v| *v == Pulse::High);
-                    let new_is_low =
-                        new_inputs.values().all(|v| *v == Pulse::High);
-                    if old_was_low != new_is_low {
-                        println!(
-                            "{key} changed from low={old_was_low} to low={new_is_low}",
-                        );
-                    }
-                }
-                _ => panic!("Unexpected discrepancy"),
-            }
-        }
-    }
-}
-
 #[derive(Clone, Debug)]
 struct Module {
     state: ModuleState,
@@ -120,7 +99,6 @@ impl Module {
             ModuleState::Broadcast => Some(pulse),
             ModuleState::SandMover(ref mut is_on) => {
                 if pulse == Pulse::Low {
-                    println!("Sand mover on!"); // TODO
                     *is_on = true;
                 }
                 None
@@ -164,14 +142,14 @@ impl Network {
             }
         }
 
-        if modules.contains_key("rx") {
+        if modules.contains_key(SAND_MOVER) {
             return Err(invalid_input(
                 "rx module cannot be specified in input",
             ));
         }
 
         modules.insert(
-            "rx".to_owned(),
+            SAND_MOVER.to_owned(),
             Module {
                 state: ModuleState::SandMover(false),
                 destinations: Vec::new(),
@@ -185,22 +163,13 @@ impl Network {
         })
     }
 
-    fn send_pulse(
-        &mut self,
-        source: &str,
-        destination: &str,
-        pulse: Pulse,
-        button_presses: usize,
-    ) {
+    // Calls `on_pulse` with the source, destination and type of every pulse
+    fn press_button(&mut self, mut on_pulse: impl FnMut(&str, &str, Pulse)) {
         let mut queue = VecDeque::<(String, String, Pulse)>::new();
-        queue.push_back((source.to_owned(), destination.to_owned(), pulse));
+        queue.push_back(("".to_owned(), "broadcaster".to_owned(), Pulse::Low));
 
         while let Some((source, destination, pulse)) = queue.pop_front() {
-            // println!("Processing pulse {:#?} from {source} to {destination}",
-            // pulse); // TODO TODO
-            if pulse == Pulse::Low && source == "zp" {
-                println!("low from zp during press {button_presses}");
-            }
+            on_pulse(&source, &destination, pulse);
 
             *match pulse {
                 Pulse::Low => &mut self.num_low_pulses,
@@ -209,15 +178,12 @@ impl Network {
 
             let Some(module) = self.modules.get_mut(&destination) else {
                 // Ignore references to invalid modules
-                //println!("Skipping pulse because {destination} doesn't
-                // exist"); // TODO
                 continue;
             };
 
             let Some(resulting_pulse) = module.handle_pulse(pulse, &source)
             else {
                 // If no resulting pulse, move on
-                //println!("No output pulse from {destination}"); // TODO
                 continue;
             };
 
@@ -232,22 +198,116 @@ impl Network {
     }
 
     fn sand_mover_is_on(&self) -> bool {
-        // TODO refactor constant
-        match self.modules.get("rx").unwrap().state {
-            ModuleState::SandMover(true) => true,
-            _ => false,
-        }
+        matches!(self.modules[SAND_MOVER].state, ModuleState::SandMover(true))
     }
-}
 
-fn _debug_compare(
-    old_modules: &HashMap<String, Module>,
-    new_modules: &HashMap<String, Module>,
-) {
-    for key in old_modules.keys() {
-        let old_mod = old_modules.get(key).unwrap();
-        let new_mod = new_modules.get(key).unwrap();
-        old_mod.state._debug_compare(key, &new_mod.state);
+    // rx is fed by one conjunction, so it gets a low pulse when every input
+    // of that conjunction sends it a high pulse during the same press. Each
+    // input has to send them in a clean cycle: a single high pulse, followed
+    // by a low one, every so many presses. The answer is then the first press
+    // that is in every input's cycle, assuming that the high pulses overlap
+    // within that press, as they do when each input is the last thing in its
+    // part of the network to update.
+    fn presses_until_sand_mover_on(&mut self) -> io::Result<usize> {
+        let feeders = self
+            .modules
+            .iter()
+            .filter(|(_, module)| {
+                module
+                    .destinations
+                    .iter()
+                    .any(|destination| destination == SAND_MOVER)
+            })
+            .collect::<Vec<_>>();
+        let [(feeder_name, feeder)] = feeders[..] else {
+            return Err(invalid_input("Expected one module to feed rx"));
+        };
+        let ModuleState::Conjunction(ref inputs) = feeder.state else {
+            return Err(invalid_input(
+                "Expected rx to be fed by a conjunction",
+            ));
+        };
+        let feeder_name = feeder_name.clone();
+        let mut input_names = inputs.keys().cloned().collect::<Vec<_>>();
+        input_names.sort();
+
+        // The presses during which each input sent a high pulse
+        let mut high_presses = vec![Vec::<usize>::new(); input_names.len()];
+        for press in 1..=MAX_BUTTON_PRESSES {
+            let mut num_high_pulses = vec![0; input_names.len()];
+            let mut last_pulses = vec![None; input_names.len()];
+            self.press_button(|source, destination, pulse| {
+                if destination != feeder_name {
+                    return;
+                }
+                if let Some(index) =
+                    input_names.iter().position(|name| name == source)
+                {
+                    if pulse == Pulse::High {
+                        num_high_pulses[index] += 1;
+                    }
+                    last_pulses[index] = Some(pulse);
+                }
+            });
+            if self.sand_mover_is_on() {
+                return Ok(press);
+            }
+
+            for (index, name) in input_names.iter().enumerate() {
+                if num_high_pulses[index] == 0 {
+                    continue;
+                }
+                if num_high_pulses[index] > 1
+                    || last_pulses[index] != Some(Pulse::Low)
+                {
+                    return Err(invalid_input(format!(
+                        "{name} doesn't send single high pulses to \
+                         {feeder_name}"
+                    )));
+                }
+                high_presses[index].push(press);
+            }
+            if high_presses
+                .iter()
+                .all(|presses| presses.len() >= CYCLES_TO_CHECK)
+            {
+                break;
+            }
+        }
+
+        let mut congruences = Vec::new();
+        for (name, presses) in input_names.iter().zip(&high_presses) {
+            let &[first, second, ..] = &presses[..] else {
+                return Err(invalid_input(format!(
+                    "{name} doesn't send {feeder_name} high pulses often \
+                     enough"
+                )));
+            };
+            let period = second - first;
+            if presses.windows(2).any(|pair| pair[1] - pair[0] != period) {
+                return Err(invalid_input(format!(
+                    "{name} doesn't send {feeder_name} high pulses in a \
+                     clean cycle"
+                )));
+            }
+            congruences.push((first as i128, period as i128));
+        }
+
+        let (residue, modulus) = chinese_remainder(&congruences)
+            .ok_or_else(|| invalid_input("The cycles never line up"))?;
+        // The first press in every cycle, which can't be before any of them
+        // start
+        let start = congruences
+            .iter()
+            .map(|&(first, _)| first)
+            .max()
+            .unwrap_or(1);
+        let presses = if residue < start {
+            residue + (start - residue + modulus - 1) / modulus * modulus
+        } else {
+            residue
+        };
+        Ok(presses as usize)
     }
 }
 
@@ -259,55 +319,13 @@ pub fn run<R: io::Read>(
 
     match part {
         Part::Part1 => {
-            for i in 0..1000 {
-                network.send_pulse("", "broadcaster", Pulse::Low, i + 1); // TODO: use a constant
+            for _ in 0..BUTTON_PRESSES {
+                network.press_button(|_, _, _| {});
             }
             println!("{}", network.num_high_pulses * network.num_low_pulses);
         }
         Part::Part2 => {
-            let mut button_presses: usize = 0;
-            //let mut last_pz = false;
-            while !network.sand_mover_is_on() {
-                // TODO
-                //let old_modules = network.modules.clone();
-                network.send_pulse(
-                    "",
-                    "broadcaster",
-                    Pulse::Low,
-                    button_presses + 1,
-                ); // TODO dup
-                button_presses += 1;
-                //println!("After {button_presses} presses:");
-                //_debug_compare(&old_modules, &network.modules);
-                //println!();
-
-                // TODO
-                /*let check_conj = |name: &str, want: Pulse| {
-                    if let Some(Module { state: ModuleState::Conjunction(ref inputs), ..}) = network.modules.get(name) {
-                        let is_outputting_low = inputs.values().all(|li| *li == Pulse::High);
-                        match (is_outputting_low, want) {
-                            (true, Pulse::Low) => println!("{name} is outputting high after {button_presses} presses"),
-                            (false, Pulse::High) => println!("{name} is outputting low after {button_presses} presses"),
-                            _ => (),
-                        }
-                    }
-                };
-                check_conj("nx", Pulse::Low);*/
-                // check_conj("bh", Pulse::High);
-                // check_conj("dl", Pulse::High);
-                // check_conj("ns", Pulse::High);
-                // check_conj("vd", Pulse::High);
-                /*let check_ff = |name: &str, last: &mut bool| {
-                    if let Some(Module { state: ModuleState::FlipFlop(is_on), ..}) = network.modules.get(name) {
-                        if last != is_on {
-                            println!("{name} is_on={is_on} after {button_presses} presses");
-                            *last = *is_on;
-                        }
-                    }
-                };
-                check_ff("jl", &mut last_pz);*/
-            }
-            println!("{button_presses}");
+            println!("{}", network.presses_until_sand_mover_on()?);
         }
     }
 
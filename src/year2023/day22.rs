@@ -3,40 +3,19 @@ use std::collections::HashSet;
 use std::io;
 
 use crate::errors::invalid_input;
+use crate::geometry::Point3D;
 use crate::parse::lines;
 use crate::part::Part;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
-struct Point {
-    x: usize,
-    y: usize,
-    z: usize,
-}
-
-impl Point {
-    fn from_string(string: &str) -> io::Result<Self> {
-        let [x_str, y_str, z_str] = string.split(",").collect::<Vec<_>>()[..]
-        else {
-            return Err(invalid_input("Expected <x>,<y>,<z>"));
-        };
-
-        let x = x_str.parse::<usize>().map_err(invalid_input)?;
-        let y = y_str.parse::<usize>().map_err(invalid_input)?;
-        let z = z_str.parse::<usize>().map_err(invalid_input)?;
-
-        Ok(Self { x, y, z })
-    }
-}
-
-const Z_ON_GROUND: usize = 1;
+const Z_ON_GROUND: i64 = 1;
 
 // For each brick, we keep track of a set of blocks that it occupies. This isn't
 // very fast, but it's fast enough for the inputs we're dealing with.
 #[derive(Clone, Debug)]
 struct Brick {
-    start: Point,
-    end: Point,
-    blocks: HashSet<Point>,
+    start: Point3D,
+    end: Point3D,
+    blocks: HashSet<Point3D>,
 }
 
 impl Brick {
@@ -46,8 +25,8 @@ impl Brick {
         else {
             return Err(invalid_input("Expected <start>~<end>"));
         };
-        let start = Point::from_string(start_str)?;
-        let end = Point::from_string(end_str)?;
+        let start = start_str.parse::<Point3D>()?;
+        let end = end_str.parse::<Point3D>()?;
 
         let num_equal = (start.x == end.x) as u64
             + (start.y == end.y) as u64
@@ -62,10 +41,10 @@ impl Brick {
         })
     }
 
-    fn _blocks(start: Point, end: Point) -> HashSet<Point> {
+    fn _blocks(start: Point3D, end: Point3D) -> HashSet<Point3D> {
         if start.x != end.x {
             (min(start.x, end.x)..=max(start.x, end.x))
-                .map(|x| Point {
+                .map(|x| Point3D {
                     x,
                     y: start.y,
                     z: start.z,
@@ -73,7 +52,7 @@ impl Brick {
                 .collect()
         } else if start.y != end.y {
             (min(start.y, end.y)..=max(start.y, end.y))
-                .map(|y| Point {
+                .map(|y| Point3D {
                     x: start.x,
                     y,
                     z: start.z,
@@ -81,7 +60,7 @@ impl Brick {
                 .collect()
         } else if start.z != end.z {
             (min(start.z, end.z)..=max(start.z, end.z))
-                .map(|z| Point {
+                .map(|z| Point3D {
                     x: start.x,
                     y: start.y,
                     z,
@@ -92,11 +71,11 @@ impl Brick {
         }
     }
 
-    fn fallen_blocks(&self) -> Option<impl Iterator<Item = Point> + '_> {
+    fn fallen_blocks(&self) -> Option<impl Iterator<Item = Point3D> + '_> {
         if min(self.start.z, self.end.z) == Z_ON_GROUND {
             None
         } else {
-            Some(self.blocks.iter().map(|block| Point {
+            Some(self.blocks.iter().map(|block| Point3D {
                 x: block.x,
                 y: block.y,
                 z: block.z - 1,
@@ -119,12 +98,12 @@ impl Brick {
 #[derive(Clone, Debug)]
 struct Snapshot {
     bricks: Vec<Brick>,
-    occupied: HashSet<Point>,
+    occupied: HashSet<Point3D>,
 }
 
 impl Snapshot {
     fn new(bricks: Vec<Brick>) -> io::Result<Self> {
-        let mut occupied = HashSet::<Point>::new();
+        let mut occupied = HashSet::<Point3D>::new();
         for brick in &bricks {
             for block in &brick.blocks {
                 if !occupied.insert(*block) {
@@ -1,20 +1,11 @@
-//use std::cmp::max;
-//use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::HashMap;
 use std::io;
 
-//use crate::errors::invalid_input;
-//use crate::parse::lines;
+use crate::branch_and_bound::{BranchAndBound, Problem};
+use crate::cellmap::{Cell, CellMap, Direction, Position};
+use crate::errors::invalid_input;
 use crate::part::Part;
 
-/*
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
-enum Direction {
-    Left,
-    Right,
-    Up,
-    Down,
-}
-
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 enum Tile {
     Path,
@@ -22,225 +13,255 @@ enum Tile {
     Slope(Direction),
 }
 
-impl Tile {
-    fn from_char(ch: char) -> io::Result<Self> {
+impl Cell for Tile {
+    fn from_char(ch: char) -> Option<Self> {
         match ch {
-            '.' => Ok(Self::Path),
-            '#' => Ok(Self::Forest),
-            '^' => Ok(Self::Slope(Direction::Up)),
-            '>' => Ok(Self::Slope(Direction::Right)),
-            'v' => Ok(Self::Slope(Direction::Down)),
-            '<' => Ok(Self::Slope(Direction::Left)),
-            _ => Err(invalid_input("Invalid tile character")),
+            '.' => Some(Self::Path),
+            '#' => Some(Self::Forest),
+            '^' => Some(Self::Slope(Direction::Up)),
+            '>' => Some(Self::Slope(Direction::Right)),
+            'v' => Some(Self::Slope(Direction::Down)),
+            '<' => Some(Self::Slope(Direction::Left)),
+            _ => None,
         }
     }
-}
-
-// TODO: Bounds checking??
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
-struct Position {
-    row: isize,
-    col: isize,
-}
 
-impl Position {
-    fn moved(self, direction: Direction) -> Self {
-        match direction {
-            Direction::Left => Self {
-                row: self.row,
-                col: self.col - 1,
-            },
-            Direction::Right => Self {
-                row: self.row,
-                col: self.col + 1,
-            },
-            Direction::Up => Self {
-                row: self.row - 1,
-                col: self.col,
-            },
-            Direction::Down => Self {
-                row: self.row + 1,
-                col: self.col,
-            },
+    fn to_char(self) -> char {
+        match self {
+            Self::Path => '.',
+            Self::Forest => '#',
+            Self::Slope(Direction::Up) => '^',
+            Self::Slope(Direction::Right) => '>',
+            Self::Slope(Direction::Down) => 'v',
+            Self::Slope(Direction::Left) => '<',
         }
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 struct Edge {
     vertex_index: usize,
     length: usize,
 }
 
-struct Vertex {
-    neighbors: Vec<Edge>,
-}
-
+// The trails collapsed into a graph whose vertices are the start, the goal and
+// every junction, with an edge for each corridor between them
 struct HikingTrails {
-    vertices: Vec<Vertex>,
+    neighbors: Vec<Vec<Edge>>,
+    // The longest corridor leading into each vertex
+    longest_entries: Vec<usize>,
     starting_vertex_index: usize,
     goal_vertex_index: usize,
-    /*map: Vec<Vec<Tile>>,
-    starting_position: Position,
-    goal_position: Position,
-    width: usize,
-    height: usize,*/
+    // The only vertex leading to the goal, if there is just one
+    goal_entrance: Option<usize>,
 }
 
 impl HikingTrails {
-    fn from_reader<R: io::Read>(reader: io::BufReader<R>) -> io::Result<Self> {
-        let mut map = Vec::<Vec<Tile>>::new();
-        let mut width: Option<usize> = None;
-        let mut starting_position: Option<Position> = None;
-        let mut goal_position: Option<Position> = None;
-
-        for line in lines(reader)? {
-            let mut row = Vec::<Tile>::new();
-            for ch in line.chars() {
-                let tile = Tile::from_char(ch)?;
-                if map.is_empty() && tile == Tile::Path {
-                    match starting_position {
-                        None => {
-                            starting_position = Some(Position {
-                                row: map.len() as isize,
-                                col: row.len() as isize,
-                            });
-                        }
-                        Some(_) => {
-                            return Err(invalid_input(
-                                "Multiple starting positions",
-                            ));
-                        }
-                    }
-                }
-                if tile == Tile::Path {
-                    goal_position = Some(Position {
-                        row: map.len() as isize,
-                        col: row.len() as isize,
-                    });
-                }
-                row.push(tile);
+    fn new(map: &CellMap<Tile>, can_climb_slopes: bool) -> io::Result<Self> {
+        let is_open = |position| {
+            map.at(position).is_some_and(|tile| tile != Tile::Forest)
+        };
+        let find_end = |row| {
+            let mut ends = (0..map.width())
+                .map(|col| Position { row, col })
+                .filter(|&position| is_open(position));
+            match (ends.next(), ends.next()) {
+                (Some(end), None) => Ok(end),
+                _ => Err(invalid_input("Expected one opening per end row")),
             }
-            if let Some(the_width) = width {
-                if row.len() != the_width {
-                    return Err(invalid_input("Differing row widths"));
+        };
+        let start = find_end(0)?;
+        let goal = find_end(map.height() - 1)?;
+
+        let mut vertex_indices = HashMap::<Position, usize>::new();
+        let mut vertices = Vec::new();
+        for position in [start, goal].into_iter().chain(
+            map.all_positions().filter(|&position| {
+                is_open(position)
+                    && position
+                        .four_neighbors()
+                        .into_iter()
+                        .filter(|&neighbor| is_open(neighbor))
+                        .count()
+                        >= 3
+            }),
+        ) {
+            vertex_indices.entry(position).or_insert_with(|| {
+                vertices.push(position);
+                vertices.len() - 1
+            });
+        }
+
+        // Whether a step from `from` in `direction` is allowed
+        let can_step = |from: Position, direction: Direction| {
+            let to = from.move_one(direction);
+            if !is_open(to) {
+                return false;
+            }
+            can_climb_slopes
+                || [map.at(from), map.at(to)].into_iter().all(|tile| {
+                    !matches!(tile, Some(Tile::Slope(slope)) if slope != direction)
+                })
+        };
+
+        // Follow each corridor leaving each vertex until it reaches another
+        let neighbors = vertices
+            .iter()
+            .map(|&vertex| {
+                Direction::ALL
+                    .into_iter()
+                    .filter_map(|first_direction| {
+                        let mut previous = vertex;
+                        let mut direction = first_direction;
+                        let mut length = 0;
+                        loop {
+                            if !can_step(previous, direction) {
+                                return None;
+                            }
+                            let position = previous.move_one(direction);
+                            length += 1;
+                            if let Some(&vertex_index) =
+                                vertex_indices.get(&position)
+                            {
+                                return Some(Edge {
+                                    vertex_index,
+                                    length,
+                                });
+                            }
+                            // Not a junction, so there is at most one way on
+                            direction =
+                                Direction::ALL.into_iter().find(|&next| {
+                                    position.move_one(next) != previous
+                                        && is_open(position.move_one(next))
+                                })?;
+                            previous = position;
+                        }
+                    })
+                    .collect()
+            })
+            .collect::<Vec<Vec<Edge>>>();
+
+        let goal_vertex_index = vertex_indices[&goal];
+        let mut longest_entries = vec![0; vertices.len()];
+        let mut goal_entrances = Vec::new();
+        for (vertex_index, edges) in neighbors.iter().enumerate() {
+            for edge in edges {
+                let longest = &mut longest_entries[edge.vertex_index];
+                *longest = (*longest).max(edge.length);
+                if edge.vertex_index == goal_vertex_index {
+                    goal_entrances.push(vertex_index);
                 }
-            } else {
-                width = Some(row.len());
             }
-            map.push(row);
         }
 
-        let height = map.len();
-        if height == 0 || width.unwrap() == 0 {
-            return Err(invalid_input("Empty map"));
-        }
+        Ok(Self {
+            neighbors,
+            longest_entries,
+            starting_vertex_index: vertex_indices[&start],
+            goal_vertex_index,
+            goal_entrance: match goal_entrances[..] {
+                [entrance] => Some(entrance),
+                _ => None,
+            },
+        })
+    }
 
-        let width = width.unwrap();
+    fn longest_hike(&self) -> io::Result<usize> {
+        if self.neighbors.len() > 64 {
+            return Err(invalid_input("Too many junctions"));
+        }
 
-        let Some(starting_position) = starting_position else {
-            return Err(invalid_input("No starting position"));
-        };
-        let Some(goal_position) = goal_position else {
-            return Err(invalid_input("No goal position"));
+        let hike = Hike {
+            vertex_index: self.starting_vertex_index,
+            visited: 1 << self.starting_vertex_index,
+            length: 0,
         };
+        BranchAndBound::new(self)
+            .maximize(hike)
+            .best
+            .ok_or_else(|| invalid_input("No path to the goal"))
+    }
+}
 
-        // Convert to graph
-        let mut vertices = Vec::<Vertex>::new();
-        let mut vertex_locations = HashMap::<Position, usize>::new();
-        let mut occupied_by_edges = HashSet::<Position>::new();
-        vertices.push(Vertex { neighbors: Vec::new() });
-        vertex_locations.insert(starting_position, 0);
-
-        if goal_position != starting_position {
-            vertices.push(Vertex { neighbors: Vec::new() });
-            vertex_locations.insert(goal_position, 1);
-        }
+#[derive(Clone, Copy, Debug)]
+struct Hike {
+    vertex_index: usize,
+    // Bit i is set once vertex i has been visited
+    visited: u64,
+    length: usize,
+}
 
-        let mut paths = VecDeque::<Position>::new();
-        paths.push_back(starting_position);
+impl Problem for HikingTrails {
+    type State = Hike;
+    type Move = Edge;
+    type Value = usize;
 
-        while let Some(position) = paths.pop_back() {
-            // TODO
-            // check neighbors
-            // if only 1, continue the current edge that way
-            // if more than 1, finsh the edge and create a vertex if there isn't one here
-            // add to occupied_by_edges as appropriate
+    fn moves(&self, hike: &Hike) -> Vec<Edge> {
+        if hike.vertex_index == self.goal_vertex_index {
+            return Vec::new();
         }
-
-        Ok(Self {
-            vertices,
-            starting_vertex_index: 0,
-            goal_vertex_index: if goal_position != starting_position { 1 } else { 0 },
-        })
+        self.neighbors[hike.vertex_index]
+            .iter()
+            .filter(|edge| hike.visited & (1 << edge.vertex_index) == 0)
+            // Going anywhere else from the goal's only entrance would mean
+            // never reaching the goal
+            .filter(|edge| {
+                self.goal_entrance != Some(hike.vertex_index)
+                    || edge.vertex_index == self.goal_vertex_index
+            })
+            .copied()
+            .collect()
     }
 
-    fn tile_at(&self, position: Position) -> Option<Tile> {
-        if position.row < 0 || position.row >= self.height as isize ||
-           position.col < 0 || position.col >= self.width as isize {
-           None
-        } else {
-            Some(self.map[position.row as usize][position.col as usize])
+    fn apply(&self, hike: &Hike, edge: &Edge) -> Hike {
+        Hike {
+            vertex_index: edge.vertex_index,
+            visited: hike.visited | (1 << edge.vertex_index),
+            length: hike.length + edge.length,
         }
     }
 
-    fn longest_hike(&self, can_climb_slopes: bool) -> usize {
-        let mut longest_distance = 0usize;
-        let mut visit_queue = VecDeque::<(Position, HashSet<Position>, usize)>::new(); // TODO: This is probably slow
-        visit_queue.push_back((self.starting_position, HashSet::new(), 0));
-
-        while let Some((position, mut visited, mut num_visited)) = visit_queue.pop_back() {
-            if position == self.goal_position {
-                /*if num_visited > longest_distance {
-                    println!("Longest distance is now {num_visited}");
-                }*/
-                longest_distance = max(longest_distance, num_visited);
-                continue;
-            }
-
-            visited.insert(position);
-            num_visited += 1;
+    fn objective(&self, hike: &Hike) -> Option<usize> {
+        (hike.vertex_index == self.goal_vertex_index).then_some(hike.length)
+    }
 
-            let directions = if can_climb_slopes {
-                vec![Direction::Left, Direction::Right, Direction::Up, Direction::Down]
-            } else {
-                match self.tile_at(position) {
-                    Some(Tile::Path) => vec![Direction::Left, Direction::Right, Direction::Up, Direction::Down],
-                    Some(Tile::Slope(direction)) => vec![direction],
-                    _ => panic!("Unexpected tile type - invalid internal state")
-                }
-            };
-
-            let mut to_visit = Vec::<Position>::new();
-            for direction in directions {
-                let neighbor_position = position.moved(direction);
-                let can_visit_neighbor = !visited.contains(&neighbor_position) && matches!(self.tile_at(neighbor_position), Some(Tile::Path | Tile::Slope(_)));
-                if can_visit_neighbor {
-                    to_visit.push(neighbor_position);
+    // Only vertices still reachable without revisiting any can be visited,
+    // each entered by one of its corridors, and the longest one is the best
+    // case. There's no hope at all if the goal isn't one of them.
+    fn bound(&self, hike: &Hike) -> usize {
+        let mut reachable = 0u64;
+        let mut to_visit = vec![hike.vertex_index];
+        while let Some(vertex_index) = to_visit.pop() {
+            for edge in &self.neighbors[vertex_index] {
+                let bit = 1 << edge.vertex_index;
+                if (hike.visited | reachable) & bit == 0 {
+                    reachable |= bit;
+                    to_visit.push(edge.vertex_index);
                 }
             }
-
-            if to_visit.len() == 0 {
-                continue;
-            }
-
-            for i in 0..(to_visit.len() - 1) {
-                visit_queue.push_back((to_visit[i], visited.clone(), num_visited));
-            }
-            visit_queue.push_back((to_visit[to_visit.len() - 1], visited, num_visited));
+        }
+        if hike.vertex_index != self.goal_vertex_index
+            && reachable & (1 << self.goal_vertex_index) == 0
+        {
+            return 0;
         }
 
-        longest_distance
+        hike.length
+            + (0..self.neighbors.len())
+                .filter(|&vertex_index| reachable & (1 << vertex_index) != 0)
+                .map(|vertex_index| self.longest_entries[vertex_index])
+                .sum::<usize>()
     }
-}*/
+}
 
 pub fn run<R: io::Read>(
-    _part: Part,
-    _reader: io::BufReader<R>,
+    part: Part,
+    reader: io::BufReader<R>,
 ) -> io::Result<()> {
-    //let trails = HikingTrails::from_reader(reader)?;
+    let map = CellMap::<Tile>::new(reader)?;
+    let trails = HikingTrails::new(&map, part == Part::Part2)?;
 
-    let result = 0usize; //trails.longest_hike(part == Part::Part2);
+    let result = trails.longest_hike()?;
 
     println!("{result}");
 
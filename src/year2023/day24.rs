@@ -1,210 +1,249 @@
 use std::io;
-//use std::ops::{Add, RangeInclusive};
+use std::ops::RangeInclusive;
 
-//use bigdecimal::{Signed, Zero};
+use num_bigint::BigInt;
+use num_rational::Ratio;
 
-//use crate::errors::invalid_input;
-//use crate::parse::{lines, parse_all};
+use crate::errors::invalid_input;
+use crate::matrix::{Matrix, Solution};
+use crate::parse::{lines, parse_all};
 use crate::part::Part;
 
-/*
-//type VecN = f64;
-//const ZERO: VecN = 0.0;
-//type VecN = isize;
-//const ZERO: VecN = 0;
-type VecN = bigdecimal::BigDecimal;
-const ZERO: VecN = VecN::zero();
-
-#[derive(Clone, Debug)]
-struct Vector {
-    x: VecN,
-    y: VecN,
-    z: VecN,
+// Input coordinates are around 10^14, so products of a coordinate and a
+// velocity still fit comfortably
+type Num = i128;
+type Vector = [Num; 3];
+
+const X: usize = 0;
+const Y: usize = 1;
+const Z: usize = 2;
+
+fn cross(a: Vector, b: Vector) -> Vector {
+    [
+        a[Y] * b[Z] - a[Z] * b[Y],
+        a[Z] * b[X] - a[X] * b[Z],
+        a[X] * b[Y] - a[Y] * b[X],
+    ]
 }
 
-impl Vector {
-    fn from_string(string: &str) -> io::Result<Self> {
-        let [x, y, z] = parse_all::<_, VecN>(string.split(",").map(str::trim))?[..] else {
-            return Err(invalid_input("Expected <x>, <y>, <z>"));
-        };
-        Ok(Self { x, y, z })
-    }
+fn sub(a: Vector, b: Vector) -> Vector {
+    [a[X] - b[X], a[Y] - b[Y], a[Z] - b[Z]]
 }
 
-impl Add for Vector {
-    type Output = Self;
-
-    fn add(self, other: Self) -> Self {
-        Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
-            z: self.z + other.z,
-        }
-    }
+fn parse_vector(string: &str) -> io::Result<Vector> {
+    let &[x, y, z] = &parse_all(string.split(',').map(str::trim))?[..] else {
+        return Err(invalid_input("Expected <x>, <y>, <z>"));
+    };
+    Ok([x, y, z])
 }
 
-struct TwoDBox {
-    x: RangeInclusive<VecN>,
-    y: RangeInclusive<VecN>,
+// The square of the X-Y plane that paths have to cross in (inclusive)
+struct TestArea {
+    low: Num,
+    high: Num,
 }
 
-impl TwoDBox {
-    fn contains(&self, vector: Vector) -> bool {
-        self.x.contains(&vector.x) && self.y.contains(&vector.y)
-    }
+const PUZZLE_TEST_AREA: TestArea = TestArea {
+    low: 200_000_000_000_000,
+    high: 400_000_000_000_000,
+};
 
-    fn contains_intersection(&self, intersection: Intersection) -> bool {
-        match intersection {
-            Intersection::Point(vector) => self.contains(vector),
-            Intersection::Line(pos, vel) => {
-                panic!("Same line!");
-            }
-        }
-    }
-}
+const EXAMPLE_TEST_AREA: TestArea = TestArea { low: 7, high: 27 };
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 struct Hailstone {
-    initial_position: Vector,
+    position: Vector,
     velocity: Vector,
 }
 
-enum Intersection {
-    Point(Vector),
-    Line(Vector, Vector),
-}
-
 impl Hailstone {
     fn from_line(line: impl AsRef<str>) -> io::Result<Self> {
-        let [position_str, velocity_str] = &line.as_ref().split(" @ ").collect::<Vec<_>>()[..] else {
+        let [position, velocity] =
+            &line.as_ref().split(" @ ").collect::<Vec<_>>()[..]
+        else {
             return Err(invalid_input("Expected <position> @ <velocity>"));
         };
-        Ok(Self {
-            initial_position: Vector::from_string(position_str)?,
-            velocity: Vector::from_string(velocity_str)?,
-        })
+        let hailstone = Self {
+            position: parse_vector(position)?,
+            velocity: parse_vector(velocity)?,
+        };
+        if hailstone.velocity[X] == 0 && hailstone.velocity[Y] == 0 {
+            return Err(invalid_input("Hailstone doesn't move in X-Y"));
+        }
+        Ok(hailstone)
     }
 
-    //fn simple_velocity(&self) -> Vector {
-        // TODO rename
-        //let the_gcd = gcd(self.x, other.x
-        //self.velocity
-    //}
-
-    /*fn two_d_path_in_box(&self, the_box: &TwoDBox) -> Path {
-        let start = self.initial_position.flatten_two_d();
-    }*/
-    fn two_d_intersection(&self, other: &Hailstone) -> Vec<Intersection> {
-        // p + xv = p' + x'v'
-        // y = yi + yv * (x - xi) / xv
-        // y * xv = yi * xv + yv * x - yv * xi
-        // (xv) * y + (-yv) * x + (yv * xi - yi * xv) = 0
-        //if denominator == 0 {
-            //None
-        //} else {
-            //Some(Vector {
-                //x:
-        //}
-        let p_this = self.initial_position + self.velocity;
-        let p_other = other.initial_position + other.velocity;
-
-        let denominator = (self.initial_position.x - p_this.x) * (other.initial_position.y - p_other.y) - (self.initial_position.y - p_this.y) * (other.initial_position.x - p_other.x);
-        if denominator == ZERO {
-            // Lines have the same slope
-            // The question is, are they the same line?
-            let x_diff = self.initial_position.x - other.initial_position.x;
-            let y_diff = self.initial_position.y - other.initial_position.y;
-            if x_diff * self.velocity.y == y_diff * self.velocity.x {
-                // They are the same line. But are they going in opposite directions?
-                let this_headed_toward_other = /* x_diff.signum() == 0 || */ self.velocity.x.signum() == -x_diff.signum();
-                let other_headed_toward_this = /* x_diff.signum() == 0 || */ other.velocity.x.signum() == x_diff.signum();
-                let mut intersections = Vec::<Intersection>::new();
-                if this_headed_toward_other {
-                    intersections.push(Intersection::Line(other.initial_position, other.velocity));
+    // The times from now on at which the X-Y path is in the area, if any
+    fn times_in_area(
+        &self,
+        area: &TestArea,
+    ) -> Option<RangeInclusive<Ratio<Num>>> {
+        let mut start = Ratio::from_integer(0);
+        let mut end = None::<Ratio<Num>>;
+        for axis in [X, Y] {
+            let (position, velocity) =
+                (self.position[axis], self.velocity[axis]);
+            if velocity == 0 {
+                if !(area.low..=area.high).contains(&position) {
+                    return None;
                 }
-                if other_headed_toward_this {
-                    intersections.push(Intersection::Line(self.initial_position, self.velocity));
-                }
-                intersections
-            } else {
-                vec![]
-            }
-        } else {
-            // TODO: integer or float math is questionable here
-            let m1 = self.initial_position.x * p_this.y - self.initial_position.y * p_this.x;
-            let m2 = other.initial_position.x * p_other.y - other.initial_position.y * p_other.x;
-
-            let x = (m1 * (other.initial_position.x - p_other.x) - m2 * (self.initial_position.x - p_this.x)) / denominator;
-            let y = (m1 * (other.initial_position.y - p_other.y) - m2 * (self.initial_position.y - p_this.y)) / denominator;
-
-            let this_x_signum = (x - self.initial_position.x).signum();
-            let this_y_signum = (y - self.initial_position.y).signum();
-            let other_x_signum = (x - other.initial_position.x).signum();
-            let other_y_signum = (y - other.initial_position.y).signum();
-
-            let forward_this_x = /* this_x_signum == 0 || */ this_x_signum == self.velocity.x.signum();
-            let forward_this_y = /* this_y_signum == 0 || */ this_y_signum == self.velocity.y.signum();
-            if forward_this_x != forward_this_y {
-                println!("this={:?}, other={:?}", &self, &other);
-                println!("(x, y) = ({x}, {y})");
-                println!("{} vs {}", (x - self.initial_position.x), self.velocity.x);
-                println!("{} vs {}", (y - self.initial_position.y), self.velocity.y);
-                panic!("fail");
-            }
-            let forward_other_x = /* other_x_signum == 0 || */ other_x_signum == other.velocity.x.signum();
-            let forward_other_y = /* other_y_signum == 0 ||*/ other_y_signum == other.velocity.y.signum();
-            if forward_other_x != forward_other_y {
-                println!("this={:?}, other={:?}", &self, &other);
-                println!("(x, y) = ({x}, {y})");
-                println!("{} vs {}", (x - other.initial_position.x), other.velocity.x);
-                println!("{} vs {}", (y - other.initial_position.y), other.velocity.y);
-                panic!("fail");
+                continue;
             }
-            assert!(forward_other_x == forward_other_y);
-
-            if forward_this_x && forward_this_y && forward_other_x && forward_other_y {
-                //println!("Intersection at ({x}, {y})");
-                vec![Intersection::Point(Vector {
-                    x,
-                    y,
-                    z: ZERO // TODO
-                })]
+            let time_at = |edge| Ratio::new(edge - position, velocity);
+            let (enter, leave) = if velocity > 0 {
+                (time_at(area.low), time_at(area.high))
             } else {
-                //println!("not all forward @ ({x}, {y}): {forward_this_x} {forward_this_y} {forward_other_x} {forward_other_y}", );
-                vec![]
+                (time_at(area.high), time_at(area.low))
+            };
+            start = start.max(enter);
+            end = Some(end.map_or(leave, |end| end.min(leave)));
+        }
+        let end = end.unwrap();
+        (start <= end).then_some(start..=end)
+    }
+
+    // Where along the X-Y path a time is, measured along an axis that it
+    // moves in. Parallel hailstones measure along the same axis.
+    fn progress(&self, time: Ratio<Num>) -> Ratio<Num> {
+        let axis = if self.velocity[X] != 0 { X } else { Y };
+        time * self.velocity[axis] + self.position[axis]
+    }
+
+    // Whether the X-Y paths from now on cross inside the area. The hailstones
+    // needn't be there at the same time.
+    fn paths_cross_in(&self, other: &Self, area: &TestArea) -> bool {
+        let (Some(times), Some(other_times)) =
+            (self.times_in_area(area), other.times_in_area(area))
+        else {
+            return false;
+        };
+
+        // Solve position + t * velocity = other.position + s * other.velocity
+        let (v, w) = (self.velocity, other.velocity);
+        let d = sub(other.position, self.position);
+        let determinant = w[X] * v[Y] - v[X] * w[Y];
+        if determinant == 0 {
+            // Parallel paths only meet if they're on the same line, and then
+            // they meet wherever their parts in the area overlap
+            if d[X] * v[Y] != d[Y] * v[X] {
+                return false;
             }
+            let span = |hailstone: &Self, times: RangeInclusive<Ratio<Num>>| {
+                let (start, end) = times.into_inner();
+                let (start, end) =
+                    (hailstone.progress(start), hailstone.progress(end));
+                start.min(end)..=start.max(end)
+            };
+            let (ours, theirs) = (span(self, times), span(other, other_times));
+            return ours.start() <= theirs.end()
+                && theirs.start() <= ours.end();
+        }
+        let t = Ratio::new(w[X] * d[Y] - w[Y] * d[X], determinant);
+        let s = Ratio::new(v[X] * d[Y] - v[Y] * d[X], determinant);
+        times.contains(&t) && other_times.contains(&s)
+    }
+}
+
+impl TestArea {
+    // The example's hailstones all start near the origin, and it uses a much
+    // smaller area than the puzzle
+    fn for_hailstones(hailstones: &[Hailstone]) -> Self {
+        let is_example = hailstones.iter().all(|hailstone| {
+            hailstone
+                .position
+                .iter()
+                .all(|&coordinate| coordinate.abs() < 1_000)
+        });
+        if is_example {
+            EXAMPLE_TEST_AREA
+        } else {
+            PUZZLE_TEST_AREA
         }
     }
-}*/
+}
+
+fn count_crossings(hailstones: &[Hailstone], area: &TestArea) -> usize {
+    (0..hailstones.len())
+        .flat_map(|i| (0..i).map(move |j| (i, j)))
+        .filter(|&(i, j)| hailstones[i].paths_cross_in(&hailstones[j], area))
+        .count()
+}
+
+// A rock thrown from P with velocity R hits hailstone i exactly when
+// (P - p_i) x (R - v_i) = 0. Expanding, P x R is the same for every hailstone,
+// so subtracting hailstone 0's equation from each other one's leaves
+// equations that are linear in P and R:
+//   P x (v_i - v_0) + (p_i - p_0) x R = p_i x v_i - p_0 x v_0
+fn rock_throw(hailstones: &[Hailstone]) -> io::Result<(Vector, Vector)> {
+    let Some((first, rest)) = hailstones.split_first() else {
+        return Err(invalid_input("No hailstones"));
+    };
+
+    let mut rows = Vec::new();
+    let mut rhs = Vec::new();
+    for hailstone in rest {
+        let dv = sub(hailstone.velocity, first.velocity);
+        let dp = sub(hailstone.position, first.position);
+        let constant = sub(
+            cross(hailstone.position, hailstone.velocity),
+            cross(first.position, first.velocity),
+        );
+        rows.extend([
+            [0, dv[Z], -dv[Y], 0, -dp[Z], dp[Y]],
+            [-dv[Z], 0, dv[X], dp[Z], 0, -dp[X]],
+            [dv[Y], -dv[X], 0, -dp[Y], dp[X], 0],
+        ]);
+        rhs.extend(constant);
+    }
+
+    let to_ratio = |value: Num| Ratio::from_integer(BigInt::from(value));
+    let matrix = Matrix::from_rows(
+        rows.into_iter().map(|row| row.into_iter().map(to_ratio)),
+    );
+    let rhs = rhs.into_iter().map(to_ratio).collect::<Vec<_>>();
+    let solution = match matrix.solve(&rhs) {
+        Solution::Unique(solution) => solution,
+        Solution::None => {
+            return Err(invalid_input("No rock hits every hailstone"));
+        }
+        Solution::Infinite { .. } => {
+            return Err(invalid_input("Too few hailstones to find the rock"));
+        }
+    };
+
+    let values = solution
+        .into_iter()
+        .map(|value| {
+            if !value.is_integer() {
+                return Err(invalid_input("The rock needs a fractional throw"));
+            }
+            Num::try_from(value.to_integer()).map_err(invalid_input)
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok((
+        [values[0], values[1], values[2]],
+        [values[3], values[4], values[5]],
+    ))
+}
 
 pub fn run<R: io::Read>(
-    _part: Part,
-    _reader: io::BufReader<R>,
+    part: Part,
+    reader: io::BufReader<R>,
 ) -> io::Result<()> {
-    /*
-    //let test_area = TwoDBox { x: 200000000000000.0..=400000000000000.0, y: 200000000000000.0..=400000000000000.0 };
-    let test_area = TwoDBox { x: 200000000000000.into()..=400000000000000.into(), y: 200000000000000.into()..=400000000000000.into() };
-    //let test_area = TwoDBox { x: 7.0..=27.0, y: 7.0..=27.0 };
-    let hailstones = lines(reader)?.map(Hailstone::from_line).collect::<io::Result<Vec<_>>>()?;
-
-    let mut result = 0usize;
-
-    for i in 0..hailstones.len() {
-        for j in 0..i {
-            let h1 = &hailstones[i];
-            let h2 = &hailstones[j];
-            for intersection in h1.two_d_intersection(h2) {
-                if test_area.contains_intersection(intersection) {
-                    result += 1;
-                    break;
-                } else{
-                    //println!("not in test area: {:?}", intersection);
-                }
-            }
+    let hailstones = lines(reader)?
+        .map(Hailstone::from_line)
+        .collect::<io::Result<Vec<_>>>()?;
+
+    match part {
+        Part::Part1 => {
+            let area = TestArea::for_hailstones(&hailstones);
+            println!("{}", count_crossings(&hailstones, &area));
+        }
+        Part::Part2 => {
+            let (position, _) = rock_throw(&hailstones)?;
+            println!("{}", position.iter().sum::<Num>());
         }
     }
 
-    println!("{result}");*/
-
     Ok(())
 }
@@ -3,54 +3,9 @@ use std::io;
 use std::io::BufRead;
 
 use crate::errors::invalid_input;
+use crate::math::gcd;
 use crate::part::Part;
 
-// From https://github.com/rust-num/num-integer/blob/03640c2a9472fad6f40845ab29c7c9502935d1d3/src/lib.rs
-// Licensed under MIT license:
-// https://github.com/rust-num/num-integer/blob/03640c2a9472fad6f40845ab29c7c9502935d1d3/LICENSE-MIT
-fn gcd(this: isize, other: isize) -> isize {
-    // Use Stein's algorithm
-    let mut m = this;
-    let mut n = other;
-    if m == 0 || n == 0 {
-        return (m | n).abs();
-    }
-
-    // find common factors of 2
-    let shift = (m | n).trailing_zeros();
-
-    // The algorithm needs positive numbers, but the minimum value
-    // can't be represented as a positive one.
-    // It's also a power of two, so the gcd can be
-    // calculated by bitshifting in that case
-
-    // Assuming two's complement, the number created by the shift
-    // is positive for all numbers except gcd = abs(min value)
-    // The call to .abs() causes a panic in debug mode
-    if m == isize::min_value() || n == isize::min_value() {
-        return (1isize << shift).abs();
-    }
-
-    // guaranteed to be positive now, rest like unsigned algorithm
-    m = m.abs();
-    n = n.abs();
-
-    // divide n and m by 2 until odd
-    m >>= m.trailing_zeros();
-    n >>= n.trailing_zeros();
-
-    while m != n {
-        if m > n {
-            m -= n;
-            m >>= m.trailing_zeros();
-        } else {
-            n -= m;
-            n >>= n.trailing_zeros();
-        }
-    }
-    m << shift
-}
-
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 struct Position {
     row: isize,
@@ -3,11 +3,16 @@ use std::io;
 use num_rational::Rational64;
 
 use crate::errors::invalid_input;
+use crate::math::extended_gcd;
+use crate::matrix::{Matrix, Solution};
 use crate::parse::{lines, paragraphs, parse_all};
 use crate::part::Part;
 
 type Num = Rational64;
 
+const A_TOKENS: Num = Num::new_raw(3, 1);
+const B_TOKENS: Num = Num::new_raw(1, 1);
+
 struct Machine {
     a_x: Num,
     a_y: Num,
@@ -44,15 +49,6 @@ impl Machine {
             prize_x += part_2_diff;
             prize_y += part_2_diff;
         }
-        if a_x == Num::ZERO
-            || a_y == Num::ZERO
-            || b_x == Num::ZERO
-            || b_y == Num::ZERO
-            || prize_x == Num::ZERO
-            || prize_y == Num::ZERO
-        {
-            return Err(invalid_input("Values cannot be 0"));
-        }
         Ok(Self {
             a_x,
             a_y,
@@ -64,21 +60,73 @@ impl Machine {
     }
 
     fn minimum_tokens(&self) -> Option<Num> {
-        let a_tokens = Num::from_integer(3);
-        let b_tokens = Num::from_integer(1);
-        if self.a_y * self.b_x == self.a_x * self.b_y {
-            unimplemented!();
+        let buttons =
+            Matrix::from_rows([[self.a_x, self.b_x], [self.a_y, self.b_y]]);
+        let (a_presses, b_presses) = match buttons
+            .solve(&[self.prize_x, self.prize_y])
+        {
+            Solution::None => return None,
+            Solution::Unique(presses) => (presses[0], presses[1]),
+            Solution::Infinite { .. } => self.cheapest_collinear_presses()?,
+        };
+        if !a_presses.is_integer()
+            || !b_presses.is_integer()
+            || a_presses < Num::ZERO
+            || b_presses < Num::ZERO
+        {
+            None
         } else {
-            let a_presses = (self.prize_y
-                - (self.b_y * self.prize_x / self.b_x))
-                / (self.a_y - self.a_x * self.b_y / self.b_x);
-            let b_presses = (self.prize_x - self.a_x * a_presses) / self.b_x;
-            if !a_presses.is_integer() || !b_presses.is_integer() {
-                None
+            Some(a_presses * A_TOKENS + b_presses * B_TOKENS)
+        }
+    }
+
+    // When both buttons move the claw along the same line, only an axis that
+    // the buttons move along matters. If just one button moves, only it is
+    // worth pressing. Otherwise the integer solutions of
+    // a * a_move + b * b_move = prize_move are
+    // (a_0 + k * a_step, b_0 - k * b_step), and the cost changes linearly in
+    // k, so the cheapest is at one end of the range where both are
+    // non-negative.
+    fn cheapest_collinear_presses(&self) -> Option<(Num, Num)> {
+        let (a_move, b_move, prize_move) =
+            if self.a_x != Num::ZERO || self.b_x != Num::ZERO {
+                (self.a_x, self.b_x, self.prize_x)
             } else {
-                Some(a_presses * a_tokens + b_presses * b_tokens)
-            }
+                (self.a_y, self.b_y, self.prize_y)
+            };
+        if a_move == Num::ZERO && b_move == Num::ZERO {
+            // Neither button moves, so the claw is already at the prize
+            return Some((Num::ZERO, Num::ZERO));
+        } else if a_move == Num::ZERO {
+            return Some((Num::ZERO, prize_move / b_move));
+        } else if b_move == Num::ZERO {
+            return Some((prize_move / a_move, Num::ZERO));
+        }
+
+        let (a_move, b_move, prize_move) = (
+            a_move.to_integer(),
+            b_move.to_integer(),
+            prize_move.to_integer(),
+        );
+        let (g, x, y) = extended_gcd(a_move, b_move);
+        if prize_move % g != 0 {
+            return None;
+        }
+        let (a_0, b_0) = (x * (prize_move / g), y * (prize_move / g));
+        let (a_step, b_step) = (b_move / g, a_move / g);
+
+        let min_k = -a_0.div_euclid(a_step);
+        let max_k = b_0.div_euclid(b_step);
+        if min_k > max_k {
+            return None;
         }
+        let cost_per_k =
+            a_step * A_TOKENS.to_integer() - b_step * B_TOKENS.to_integer();
+        let k = if cost_per_k > 0 { min_k } else { max_k };
+        Some((
+            Num::from_integer(a_0 + k * a_step),
+            Num::from_integer(b_0 - k * b_step),
+        ))
     }
 }
 
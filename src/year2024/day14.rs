@@ -1,51 +1,17 @@
 use std::collections::HashMap;
 use std::io;
-use std::ops::{Add, AddAssign};
-use std::str::FromStr;
 use std::thread::sleep;
 use std::time::Duration;
 
+use crate::cellmap::Position;
 use crate::errors::invalid_input;
-use crate::parse::{lines, parse_all};
+use crate::math::chinese_remainder;
+use crate::parse::lines;
 use crate::part::Part;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Vec2D {
-    x: i64,
-    y: i64,
-}
-
-impl FromStr for Vec2D {
-    type Err = io::Error;
-
-    fn from_str(s: &str) -> io::Result<Self> {
-        let &[x, y] = &parse_all::<_, i64>(s.split(","))?[..] else {
-            return Err(invalid_input("Expected x,y"));
-        };
-        Ok(Self { x, y })
-    }
-}
-
-impl Add for Vec2D {
-    type Output = Self;
-
-    fn add(self, other: Self) -> Self {
-        Self {
-            x: self.x + other.x,
-            y: self.y + other.y,
-        }
-    }
-}
-
-impl AddAssign for Vec2D {
-    fn add_assign(&mut self, other: Self) {
-        *self = *self + other;
-    }
-}
-
 struct Robot {
-    position: Vec2D,
-    velocity: Vec2D,
+    position: Position,
+    velocity: Position,
 }
 
 impl Robot {
@@ -69,24 +35,10 @@ impl Robot {
         })
     }
 
-    fn move_robot(&mut self, width: i64, height: i64, moves: usize) {
-        for _ in 0..moves {
-            self.position += self.velocity;
-
-            // Better hope the compiler optimizes this
-            while self.position.x < 0 {
-                self.position.x += width;
-            }
-            while self.position.x >= width {
-                self.position.x -= width;
-            }
-            while self.position.y < 0 {
-                self.position.y += height;
-            }
-            while self.position.y >= height {
-                self.position.y -= height;
-            }
-        }
+    fn move_robot(&mut self, width: isize, height: isize, moves: usize) {
+        self.position += self.velocity * moves as isize;
+        self.position.col = self.position.col.rem_euclid(width);
+        self.position.row = self.position.row.rem_euclid(height);
     }
 }
 
@@ -99,10 +51,10 @@ enum Quadrant {
 }
 
 struct Bathroom {
-    width: i64,
-    height: i64,
+    width: isize,
+    height: isize,
     robots: Vec<Robot>,
-    positions: HashMap<Vec2D, usize>,
+    positions: HashMap<Position, usize>,
 }
 
 impl Bathroom {
@@ -110,7 +62,7 @@ impl Bathroom {
         let robots = lines(reader)?
             .map(|line| Robot::from_line(&line))
             .collect::<io::Result<Vec<_>>>()?;
-        let mut positions = HashMap::<Vec2D, usize>::new();
+        let mut positions = HashMap::<Position, usize>::new();
         for robot in &robots {
             *positions.entry(robot.position).or_insert(0) += 1;
         }
@@ -122,29 +74,29 @@ impl Bathroom {
         })
     }
 
-    fn quadrant(&self, position: Vec2D) -> Option<Quadrant> {
-        if position.x < 0
-            || position.x >= self.width
-            || position.y < 0
-            || position.y >= self.height
+    fn quadrant(&self, position: Position) -> Option<Quadrant> {
+        if position.col < 0
+            || position.col >= self.width
+            || position.row < 0
+            || position.row >= self.height
         {
             return None;
         }
-        if self.width % 2 == 1 && position.x == self.width / 2 {
+        if self.width % 2 == 1 && position.col == self.width / 2 {
             return None;
         }
-        if self.height % 2 == 1 && position.y == self.height / 2 {
+        if self.height % 2 == 1 && position.row == self.height / 2 {
             return None;
         }
         Some(
-            if position.x < self.width / 2 && position.y < self.height / 2 {
+            if position.col < self.width / 2 && position.row < self.height / 2 {
                 Quadrant::TopLeft
-            } else if position.x >= self.width / 2
-                && position.y < self.height / 2
+            } else if position.col >= self.width / 2
+                && position.row < self.height / 2
             {
                 Quadrant::TopRight
-            } else if position.x < self.width / 2
-                && position.y >= self.height / 2
+            } else if position.col < self.width / 2
+                && position.row >= self.height / 2
             {
                 Quadrant::BottomLeft
             } else {
@@ -171,29 +123,70 @@ impl Bathroom {
         map.values().product()
     }
 
-    fn print(&self) {
+    // The robots' columns repeat every `width` seconds and their rows every
+    // `height` seconds. The picture is when they bunch up the most, so find
+    // when each axis has the least variance and combine the two.
+    fn seconds_until_picture(&self) -> io::Result<usize> {
+        let most_bunched = |period: isize, axis: fn(Position) -> isize| {
+            (0..period)
+                .min_by_key(|&seconds| {
+                    let values = self.robots.iter().map(|robot| {
+                        let value = axis(robot.position)
+                            + axis(robot.velocity) * seconds;
+                        value.rem_euclid(period)
+                    });
+                    // The variance times the square of the number of robots
+                    let (sum, sum_of_squares) =
+                        values.fold((0, 0), |(sum, sum_of_squares), value| {
+                            (sum + value, sum_of_squares + value * value)
+                        });
+                    self.robots.len() as isize * sum_of_squares - sum * sum
+                })
+                .unwrap_or(0)
+        };
+        let col_seconds = most_bunched(self.width, |position| position.col);
+        let row_seconds = most_bunched(self.height, |position| position.row);
+
+        let (seconds, _) = chinese_remainder(&[
+            (col_seconds as i128, self.width as i128),
+            (row_seconds as i128, self.height as i128),
+        ])
+        .ok_or_else(|| invalid_input("The picture never forms"))?;
+        Ok(seconds as usize)
+    }
+
+    fn render(&self) -> String {
+        let mut picture = String::new();
         for y in 0..self.height {
             for x in 0..self.width {
-                let pos = Vec2D { x, y };
+                let pos = Position { row: y, col: x };
                 if *self.positions.get(&pos).unwrap_or(&0) != 0 {
-                    print!("*");
+                    picture.push('*');
                 } else {
-                    print!(" ");
+                    picture.push(' ');
                 }
             }
-            println!();
+            picture.push('\n');
         }
+        picture
+    }
+
+    // Moves the robots to when they draw the picture, returning how many
+    // seconds that took and the picture
+    fn move_to_picture(&mut self) -> io::Result<(usize, String)> {
+        let seconds = self.seconds_until_picture()?;
+        self.move_robots(seconds);
+        Ok((seconds, self.render()))
     }
 
     #[allow(dead_code)]
     fn move_and_print(&mut self, moves: usize) {
-        self.print();
+        print!("{}", self.render());
         for move_num in 0..moves {
             sleep(Duration::from_millis(250));
             println!("After {} seconds:", move_num + 1);
             self.move_robots(1);
-            self.print();
-            println!();
+            println!("{}", self.render());
         }
     }
 }
@@ -204,16 +197,16 @@ pub fn run<R: io::Read>(
 ) -> io::Result<()> {
     let mut bathroom = Bathroom::new(reader)?;
 
-    match part {
-        Part::Part1 => bathroom.move_robots(100),
+    let result = match part {
+        Part::Part1 => {
+            bathroom.move_robots(100);
+            bathroom.safety_factor()
+        }
         Part::Part2 => {
-            bathroom.move_robots(7774);
-            bathroom.print();
-            // bathroom.move_and_print(10000),
+            let (seconds, _picture) = bathroom.move_to_picture()?;
+            seconds
         }
-    }
-
-    let result = bathroom.safety_factor();
+    };
 
     println!("{result}");
 
@@ -69,6 +69,16 @@ impl Bits {
     fn raw_value(self) -> i64 {
         self.value
     }
+
+    fn combo_operand_name(self) -> String {
+        match self.value {
+            0..=3 => self.value.to_string(),
+            4 => "A".to_string(),
+            5 => "B".to_string(),
+            6 => "C".to_string(),
+            _ => "<invalid>".to_string(),
+        }
+    }
 }
 
 fn skip_then_parse_i64(value: &str, prefix: &str) -> io::Result<i64> {
@@ -192,6 +202,157 @@ impl Computer {
     fn run_until_halt(&mut self) {
         while self.run_instruction() {}
     }
+
+    fn output_for(&self, register_a: i64) -> Vec<i64> {
+        let mut computer = self.clone();
+        computer.register_a = register_a;
+        computer.run_until_halt();
+        computer.output
+    }
+
+    // The program as pseudo-code, one instruction per line
+    fn disassemble(&self) -> String {
+        self.memory
+            .chunks(2)
+            .enumerate()
+            .map(|(index, chunk)| {
+                let &[opcode, operand] = chunk else {
+                    return format!("{:3}: <missing operand>", index * 2);
+                };
+                let literal = operand.raw_value();
+                let combo = operand.combo_operand_name();
+                let code = match opcode.as_instruction() {
+                    Instruction::DivideIntoA => format!("A = A >> {combo}"),
+                    Instruction::BitwiseXORLiteral => {
+                        format!("B = B ^ {literal}")
+                    }
+                    Instruction::OperandToRegister => {
+                        format!("B = {combo} % 8")
+                    }
+                    Instruction::JumpIfNotZero => {
+                        format!("if A != 0 jump to {literal}")
+                    }
+                    Instruction::BitwiseXORRegisters => "B = B ^ C".to_string(),
+                    Instruction::Output => format!("output {combo} % 8"),
+                    Instruction::DivideIntoB => format!("B = A >> {combo}"),
+                    Instruction::DivideIntoC => format!("C = A >> {combo}"),
+                };
+                format!("{:3}: {code}", index * 2)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // How many bits of A each pass through the program shifts out, if it is a
+    // single loop back to the start that shifts A by a constant once per pass,
+    // outputs something, and doesn't carry B or C over to the next pass
+    fn bits_per_pass(&self) -> Option<i64> {
+        let (body, [jump, target]) = self.memory.split_last_chunk::<2>()?;
+        if body.len() % 2 != 0
+            || jump.as_instruction() != Instruction::JumpIfNotZero
+            || target.raw_value() != 0
+        {
+            return None;
+        }
+
+        let mut shift = None;
+        let mut has_output = false;
+        let (mut b_is_set, mut c_is_set) = (false, false);
+        for chunk in body.chunks_exact(2) {
+            let (instruction, operand) = (chunk[0].as_instruction(), chunk[1]);
+            let combo_operand_is_set = match operand.raw_value() {
+                5 => b_is_set,
+                6 => c_is_set,
+                7 => false,
+                _ => true,
+            };
+            match instruction {
+                Instruction::DivideIntoA => {
+                    // A literal shift, and only one per pass
+                    if !(1..=3).contains(&operand.raw_value())
+                        || shift.is_some()
+                    {
+                        return None;
+                    }
+                    shift = Some(operand.raw_value());
+                }
+                Instruction::BitwiseXORLiteral if !b_is_set => return None,
+                Instruction::BitwiseXORRegisters if !b_is_set || !c_is_set => {
+                    return None;
+                }
+                Instruction::JumpIfNotZero => return None,
+                Instruction::OperandToRegister | Instruction::DivideIntoB
+                    if combo_operand_is_set =>
+                {
+                    b_is_set = true;
+                }
+                Instruction::DivideIntoC if combo_operand_is_set => {
+                    c_is_set = true;
+                }
+                Instruction::Output if combo_operand_is_set => {
+                    has_output = true;
+                }
+                Instruction::BitwiseXORLiteral
+                | Instruction::BitwiseXORRegisters => {}
+                _ => return None,
+            }
+        }
+        shift.filter(|_| has_output)
+    }
+
+    // The lowest value of A for which the program outputs itself. Each pass
+    // outputs something based on A and then shifts some bits out of it, so
+    // the last pass sees only the top bits of A, the pass before it sees those
+    // and some more, and so on. Working backwards from the end of the program,
+    // each candidate for a pass's A is a candidate for the next pass's with
+    // more bits below it, and is only kept if running the program from it
+    // outputs the end of the program.
+    fn lowest_quine_register_a(&self) -> io::Result<i64> {
+        let program = self
+            .memory
+            .iter()
+            .map(|bits| bits.raw_value())
+            .collect::<Vec<_>>();
+        if self.output_for(0) == program {
+            return Ok(0);
+        }
+        let Some(shift) = self.bits_per_pass() else {
+            return Err(invalid_input(format!(
+                "Can't work backwards through this program:\n{}",
+                self.disassemble()
+            )));
+        };
+
+        let mut candidates = vec![0i64];
+        while !candidates.is_empty() {
+            // Candidates stay in ascending order, so the first quine is the
+            // lowest
+            let mut next_candidates = Vec::new();
+            for &candidate in &candidates {
+                if candidate > i64::MAX >> shift {
+                    continue;
+                }
+                for low_bits in 0..1 << shift {
+                    let register_a = candidate << shift | low_bits;
+                    if register_a == 0 {
+                        continue;
+                    }
+                    let output = self.output_for(register_a);
+                    if output == program {
+                        return Ok(register_a);
+                    }
+                    if program.ends_with(&output) {
+                        next_candidates.push(register_a);
+                    }
+                }
+            }
+            candidates = next_candidates;
+        }
+
+        Err(invalid_input(
+            "No value of A makes the program output itself",
+        ))
+    }
 }
 
 pub fn run<R: io::Read>(
@@ -210,80 +371,7 @@ pub fn run<R: io::Read>(
                 .collect::<Vec<_>>();
             string_vec.join(",")
         }
-        Part::Part2 => {
-            // This solution is specific to the type of program in the sample
-            // output.
-            let mut components = Vec::<i64>::new();
-            components.push(0);
-            let final_value = loop {
-                let mut register_a_value = 0i64;
-                for &component in components.iter() {
-                    register_a_value <<= 3;
-                    register_a_value |= component;
-                }
-                let mut new_computer = computer.clone();
-                new_computer.register_a = register_a_value;
-                new_computer.run_until_halt();
-                let mut num_same: usize = 0;
-                let size_diff = computer.memory.len() - components.len();
-                if new_computer.output.len() != components.len() {
-                    panic!();
-                }
-                for i in (0..new_computer.output.len()).rev() {
-                    if new_computer.output[i]
-                        != computer.memory[size_diff + i].raw_value()
-                    {
-                        break;
-                    }
-                    num_same += 1;
-                }
-
-                if num_same == components.len() {
-                    if size_diff == 0 {
-                        break register_a_value;
-                    }
-                    components.push(0);
-                } else if num_same + 1 == components.len() {
-                    loop {
-                        *components.last_mut().unwrap() += 1;
-                        if *components.last().unwrap() > 7 {
-                            components.pop();
-                            if components.is_empty() {
-                                panic!("Ran out of possibilities");
-                            }
-                        } else {
-                            break;
-                        }
-                    }
-                } else {
-                    panic!(
-                        "Multiple differing components: {num_same} same with {} components (size diff={size_diff})",
-                        components.len()
-                    );
-                }
-            };
-            /*
-            // Generic solution:
-            let mut register_a_value = 0i64;
-            'outer: loop {
-                register_a_value += 1;
-                if register_a_value % 1_000_000 == 0 {
-                    println!("A={register_a_value}");
-                }
-                let mut new_computer = computer.clone();
-                new_computer.register_a = register_a_value;
-                new_computer.run_until_halt();
-                if new_computer.output.len() == computer.memory.len() {
-                    for i in 0..new_computer.output.len() {
-                        if new_computer.output[i] != computer.memory[i].raw_value() {
-                            continue 'outer;
-                        }
-                    }
-                    break;
-                }
-            }*/
-            final_value.to_string()
-        }
+        Part::Part2 => computer.lowest_quine_register_a()?.to_string(),
     };
 
     println!("{result}");
@@ -1,7 +1,8 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
 use std::io;
 
 use crate::errors::invalid_input;
+use crate::memo::Memo;
 use crate::parse::{lines, paragraphs};
 use crate::part::Part;
 
@@ -43,7 +44,6 @@ type Design = Pattern;
 
 struct PatternSet {
     patterns: BTreeMap<usize, HashSet<Pattern>>,
-    design_cache: HashMap<Design, usize>,
 }
 
 impl PatternSet {
@@ -62,46 +62,41 @@ impl PatternSet {
                 .insert(pattern);
         }
 
-        Self {
-            patterns,
-            design_cache: HashMap::new(),
-        }
+        Self { patterns }
     }
 
-    fn ways_possible_helper(&mut self, design: &Design) -> usize {
+    fn ways_possible(
+        &self,
+        design: &Design,
+        memo: &mut Memo<Design, usize>,
+    ) -> usize {
         let design_length = design.colors.len();
         if design_length == 0 {
             return 1;
         }
 
-        let max_pattern_length = self
-            .patterns
-            .last_key_value()
-            .map(|pair| *pair.0)
-            .unwrap_or(0)
-            .min(design_length);
-        let mut result = 0usize;
-        for pattern_length in 1..=max_pattern_length {
-            let Some(pattern_set) = self.patterns.get(&pattern_length) else {
-                continue;
-            };
-            let prefix = design.prefix(pattern_length);
-            if pattern_set.contains(&prefix) {
-                result += self.ways_possible(&design.suffix(pattern_length));
+        memo.get_or_compute(design.clone(), |memo| {
+            let max_pattern_length = self
+                .patterns
+                .last_key_value()
+                .map(|pair| *pair.0)
+                .unwrap_or(0)
+                .min(design_length);
+            let mut result = 0usize;
+            for pattern_length in 1..=max_pattern_length {
+                let Some(pattern_set) = self.patterns.get(&pattern_length)
+                else {
+                    continue;
+                };
+                let prefix = design.prefix(pattern_length);
+                if pattern_set.contains(&prefix) {
+                    result += self
+                        .ways_possible(&design.suffix(pattern_length), memo);
+                }
             }
-        }
 
-        result
-    }
-
-    fn ways_possible(&mut self, design: &Design) -> usize {
-        if let Some(&cached_value) = self.design_cache.get(&design) {
-            cached_value
-        } else {
-            let computed_value = self.ways_possible_helper(design);
-            self.design_cache.insert(design.clone(), computed_value);
-            computed_value
-        }
+            result
+        })
     }
 }
 
@@ -117,13 +112,14 @@ pub fn run<R: io::Read>(
         return Err(invalid_input("Expected first paragraph to be one line"));
     }
 
-    let mut pattern_set = PatternSet::new(&paragraphs[0][0]);
+    let pattern_set = PatternSet::new(&paragraphs[0][0]);
+    let mut memo = Memo::new();
 
     let design_ways_iterator = paragraphs[1].iter().map(|design_str| {
         let design = Design {
             colors: design_str.chars().map(|name| Color { name }).collect(),
         };
-        pattern_set.ways_possible(&design)
+        pattern_set.ways_possible(&design, &mut memo)
     });
 
     let result = match part {
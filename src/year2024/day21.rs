@@ -4,6 +4,7 @@ use std::io::BufRead;
 use std::iter::once;
 
 use crate::errors::invalid_input;
+use crate::memo::Memo;
 use crate::part::Part;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -115,14 +116,17 @@ impl Keypad {
     }
 }
 
-struct Memoizer {
+// (start, end, layer): moving the pointer on the keypad at `layer` from start
+// to end, then pressing the key there
+type PressesKey = (Position, Position, isize);
+
+struct KeypadChain {
     numeric_keypad: Keypad,
     directional_keypad: Keypad,
     num_directional_keypads: isize,
-    cache: HashMap<(Position, Position, isize), isize>,
 }
 
-impl Memoizer {
+impl KeypadChain {
     fn new(
         numeric_keypad: Keypad,
         directional_keypad: Keypad,
@@ -132,7 +136,6 @@ impl Memoizer {
             numeric_keypad,
             directional_keypad,
             num_directional_keypads,
-            cache: HashMap::new(),
         }
     }
 
@@ -144,7 +147,11 @@ impl Memoizer {
         }
     }
 
-    fn compute(&mut self, key: (Position, Position, isize)) -> isize {
+    fn compute(
+        &self,
+        key: PressesKey,
+        memo: &mut Memo<PressesKey, isize>,
+    ) -> isize {
         let (start, end, layer) = key;
 
         if layer == self.num_directional_keypads + 1 {
@@ -175,7 +182,7 @@ impl Memoizer {
             for window in path.windows(2) {
                 let begin = window[0];
                 let finish = window[1];
-                result += self.get((begin, finish, layer + 1));
+                result += self.get((begin, finish, layer + 1), memo);
             }
             if result <= 0 {
                 panic!("result is {result} (path len={})", path.len());
@@ -187,14 +194,12 @@ impl Memoizer {
         min_result
     }
 
-    fn get(&mut self, key: (Position, Position, isize)) -> isize {
-        if let Some(&result) = self.cache.get(&key) {
-            result
-        } else {
-            let computed = self.compute(key);
-            self.cache.insert(key, computed);
-            computed
-        }
+    fn get(
+        &self,
+        key: PressesKey,
+        memo: &mut Memo<PressesKey, isize>,
+    ) -> isize {
+        memo.get_or_compute(key, |memo| self.compute(key, memo))
     }
 }
 
@@ -217,11 +222,12 @@ pub fn run<R: io::Read>(
         Part::Part1 => 3,
         Part::Part2 => 26,
     };
-    let mut memoizer = Memoizer::new(
+    let keypad_chain = KeypadChain::new(
         numeric_keypad,
         directional_keypad,
         num_directional_keypads,
     );
+    let mut memo = Memo::new();
 
     let mut result = 0isize;
 
@@ -235,18 +241,26 @@ pub fn run<R: io::Read>(
         let mut positions = code
             .chars()
             .map(|ch| {
-                *memoizer.numeric_keypad.position_by_key.get(&ch).unwrap()
+                *keypad_chain
+                    .numeric_keypad
+                    .position_by_key
+                    .get(&ch)
+                    .unwrap()
             })
             .collect::<Vec<_>>();
         positions.insert(
             0,
-            *memoizer.numeric_keypad.position_by_key.get(&'A').unwrap(),
+            *keypad_chain
+                .numeric_keypad
+                .position_by_key
+                .get(&'A')
+                .unwrap(),
         );
         let mut num_positions = 0isize;
         for window in positions.windows(2) {
             let begin = window[0];
             let finish = window[1];
-            num_positions += memoizer.get((begin, finish, 1));
+            num_positions += keypad_chain.get((begin, finish, 1), &mut memo);
         }
         result += num * num_positions;
     }
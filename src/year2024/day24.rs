@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::io;
 
 use crate::errors::invalid_input;
@@ -37,9 +37,58 @@ enum WireSource {
     Constant(bool),
 }
 
+// How many pairs of gates have had their outputs swapped
+const SWAPPED_PAIRS: usize = 4;
+
+// How many random additions a repaired adder has to get right
+const ADDITION_TRIALS: usize = 100;
+
+fn wire_name(prefix: char, bit: usize) -> WireName {
+    format!("{prefix}{bit:02}")
+}
+
+// The bit number of an x or y wire
+fn input_bit(wire_name: &str) -> Option<usize> {
+    wire_name
+        .strip_prefix(['x', 'y'])
+        .and_then(|bit| bit.parse().ok())
+}
+
+// Every way of splitting the wires into pairs
+fn pairings(wire_names: &[WireName]) -> Vec<Vec<(WireName, WireName)>> {
+    let Some((first, rest)) = wire_names.split_first() else {
+        return vec![Vec::new()];
+    };
+    let mut result = Vec::new();
+    for index in 0..rest.len() {
+        let mut others = rest.to_vec();
+        let partner = others.remove(index);
+        for mut pairing in pairings(&others) {
+            pairing.push((first.clone(), partner.clone()));
+            result.push(pairing);
+        }
+    }
+    result
+}
+
+// A xorshift generator, so that the checks are repeatable
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+}
+
 struct Simulation {
     wire_sources: HashMap<WireName, WireSource>,
     wire_value_cache: HashMap<WireName, bool>,
+    wires_being_computed: HashSet<WireName>,
     z_wire_names: BTreeSet<WireName>,
 }
 
@@ -48,6 +97,7 @@ impl Simulation {
         Self {
             wire_sources: HashMap::new(),
             wire_value_cache: HashMap::new(),
+            wires_being_computed: HashSet::new(),
             z_wire_names: BTreeSet::new(),
         }
     }
@@ -59,32 +109,176 @@ impl Simulation {
         self.wire_sources.insert(name, source);
     }
 
-    fn compute(&mut self, wire_name: &WireName) -> bool {
+    // None if the wire is missing an input or depends on itself
+    fn compute(&mut self, wire_name: &WireName) -> Option<bool> {
         if let Some(&value) = self.wire_value_cache.get(wire_name) {
-            return value;
+            return Some(value);
+        }
+        if !self.wires_being_computed.insert(wire_name.to_owned()) {
+            return None;
         }
 
-        let result = match self.wire_sources.get(wire_name).unwrap() {
+        let result = match self.wire_sources.get(wire_name)? {
             WireSource::Gate(gate) => {
                 let gate = gate.clone();
-                gate.compute(
-                    self.compute(&gate.input1),
-                    self.compute(&gate.input2),
-                )
+                self.compute(&gate.input1)
+                    .zip(self.compute(&gate.input2))
+                    .map(|(input1, input2)| gate.compute(input1, input2))
             }
-            WireSource::Constant(value) => *value,
+            WireSource::Constant(value) => Some(*value),
         };
-        self.wire_value_cache.insert(wire_name.to_owned(), result);
+        self.wires_being_computed.remove(wire_name);
+        if let Some(result) = result {
+            self.wire_value_cache.insert(wire_name.to_owned(), result);
+        }
         result
     }
 
-    fn z_number(&mut self) -> u64 {
+    fn z_number(&mut self) -> Option<u64> {
         let mut result = 0u64;
         for name in self.z_wire_names.clone().into_iter().rev() {
             result <<= 1;
-            result += u64::from(self.compute(&name));
+            result += u64::from(self.compute(&name)?);
         }
-        result
+        Some(result)
+    }
+
+    fn num_input_bits(&self) -> usize {
+        self.wire_sources
+            .keys()
+            .filter(|name| name.starts_with('x'))
+            .count()
+    }
+
+    // Sets the x or y wires to the bits of a number
+    fn set_number(&mut self, prefix: char, number: u64) {
+        for bit in 0..self.num_input_bits() {
+            self.wire_sources.insert(
+                wire_name(prefix, bit),
+                WireSource::Constant(number >> bit & 1 != 0),
+            );
+        }
+        self.wire_value_cache.clear();
+    }
+
+    fn swap_outputs(&mut self, wire_name1: &WireName, wire_name2: &WireName) {
+        let source1 = self.wire_sources.remove(wire_name1).unwrap();
+        let source2 = self.wire_sources.insert(wire_name2.clone(), source1);
+        self.wire_sources
+            .insert(wire_name1.clone(), source2.unwrap());
+        self.wire_value_cache.clear();
+    }
+
+    // The wires whose gates don't fit where they are in a ripple-carry adder.
+    // Bit 0 is a half adder: z00 = x00 XOR y00, with carry x00 AND y00. Every
+    // other bit i is a full adder:
+    //   zi = (xi XOR yi) XOR carry
+    //   carry out = (xi AND yi) OR ((xi XOR yi) AND carry)
+    // and the last z wire is the last carry out.
+    fn misplaced_wires(&self) -> Vec<WireName> {
+        let num_bits = self.num_input_bits();
+        let last_z = wire_name('z', num_bits);
+
+        let mut reader_types = HashMap::<&str, HashSet<GateType>>::new();
+        for source in self.wire_sources.values() {
+            if let WireSource::Gate(gate) = source {
+                for input in [&gate.input1, &gate.input2] {
+                    reader_types
+                        .entry(input)
+                        .or_default()
+                        .insert(gate.gate_type);
+                }
+            }
+        }
+        let is_read_by = |name: &WireName, gate_type| {
+            reader_types
+                .get(name.as_str())
+                .is_some_and(|types| types.contains(&gate_type))
+        };
+        let is_sum_output =
+            |name: &WireName| name.starts_with('z') && *name != last_z;
+
+        let mut misplaced = self
+            .wire_sources
+            .iter()
+            .filter_map(|(name, source)| {
+                let WireSource::Gate(gate) = source else {
+                    return None;
+                };
+                let input_bits =
+                    (input_bit(&gate.input1), input_bit(&gate.input2));
+                // Which bit's x and y it adds, if it's one of the first gates
+                let adds_inputs = match input_bits {
+                    (Some(bit1), Some(bit2)) if bit1 == bit2 => Some(bit1),
+                    _ => None,
+                };
+                let fits = match (gate.gate_type, adds_inputs) {
+                    (GateType::Xor, Some(0)) => *name == wire_name('z', 0),
+                    (GateType::Xor, Some(_)) => is_read_by(name, GateType::Xor),
+                    (GateType::Xor, None) => is_sum_output(name),
+                    (GateType::And, Some(0)) if num_bits == 1 => {
+                        *name == last_z
+                    }
+                    (GateType::And, Some(0)) => is_read_by(name, GateType::Xor),
+                    (GateType::And, _) => is_read_by(name, GateType::Or),
+                    (GateType::Or, _) => {
+                        *name == last_z || is_read_by(name, GateType::Xor)
+                    }
+                };
+                (!fits).then(|| name.clone())
+            })
+            .collect::<Vec<_>>();
+        misplaced.sort();
+        misplaced
+    }
+
+    fn adds_correctly(&mut self, random: &mut Random) -> bool {
+        let mask = (1 << self.num_input_bits()) - 1;
+        (0..ADDITION_TRIALS).all(|_| {
+            let (x, y) = (random.next() & mask, random.next() & mask);
+            self.set_number('x', x);
+            self.set_number('y', y);
+            self.z_number() == Some(x + y)
+        })
+    }
+
+    // Finds the misplaced wires, and which of them were swapped with each
+    // other by trying each way of pairing them up until the adder works
+    fn repair_adder(&mut self) -> io::Result<Vec<WireName>> {
+        let num_bits = self.num_input_bits();
+        if num_bits == 0
+            || num_bits >= 64
+            || self.z_wire_names.len() != num_bits + 1
+        {
+            return Err(invalid_input(
+                "Expected an adder with one more z wire than x wires",
+            ));
+        }
+
+        let misplaced = self.misplaced_wires();
+        if misplaced.len() != 2 * SWAPPED_PAIRS {
+            return Err(invalid_input(format!(
+                "Expected {} misplaced wires, but found {misplaced:?}",
+                2 * SWAPPED_PAIRS
+            )));
+        }
+
+        let mut random = Random { state: 0x2024_1224 };
+        for pairing in pairings(&misplaced) {
+            for (wire_name1, wire_name2) in &pairing {
+                self.swap_outputs(wire_name1, wire_name2);
+            }
+            if self.adds_correctly(&mut random) {
+                return Ok(misplaced);
+            }
+            for (wire_name1, wire_name2) in &pairing {
+                self.swap_outputs(wire_name1, wire_name2);
+            }
+        }
+
+        Err(invalid_input(format!(
+            "No way of swapping {misplaced:?} makes the adder work"
+        )))
     }
 }
 
@@ -132,228 +326,17 @@ pub fn run<R: io::Read>(
         );
     }
 
-    if part == Part::Part2 {
-        /*
-        for x_num in 0..45 {
-            let name = format!("x{x_num:02}");
-
-            simulation.wire_value_cache.clear();
-            for (wire_name, source) in &mut simulation.wire_sources {
-                let WireSource::Constant(ref mut value) = source else {
-                    continue;
-                };
-
-                *value = false;
-            }
-            simulation.wire_sources.insert(name, WireSource::Constant(true));
-            let actual = simulation.z_number();
-            let expected = 1 << x_num;
-            if actual != expected {
-                println!("x bit {x_num} is off. expected {expected:b} but saw {actual:b}");
-            }
+    match part {
+        Part::Part1 => {
+            let result = simulation.z_number().ok_or_else(|| {
+                invalid_input("A z wire is missing an input or loops")
+            })?;
+            println!("{result}");
         }
-
-        for y_num in 0..45 {
-            let name = format!("y{y_num:02}");
-
-            simulation.wire_value_cache.clear();
-            for (wire_name, source) in &mut simulation.wire_sources {
-                let WireSource::Constant(ref mut value) = source else {
-                    continue;
-                };
-
-                *value = false;
-            }
-            simulation.wire_sources.insert(name, WireSource::Constant(true));
-            let actual = simulation.z_number();
-            let expected = 1 << y_num;
-            if actual != expected {
-                println!("y bit {y_num} is off. expected {expected:b} but saw {actual:b}");
-            }
-        }*/
-        // TODO: check 45 and 0 and 1
-        let mut expected_basic_add: Option<WireName> = None;
-        let mut expected_prev_carry: Option<WireName> = None;
-
-        for z_num in (2..45).rev() {
-            let name = format!("z{z_num:02}");
-            let x_name = format!("x{z_num:02}");
-            let y_name = format!("y{z_num:02}");
-
-            let WireSource::Gate(gate) =
-                simulation.wire_sources.get(&name).unwrap()
-            else {
-                println!("Expected {name} to be gate-sourced");
-                expected_basic_add = None;
-                expected_prev_carry = None;
-                continue;
-            };
-            if gate.gate_type != GateType::Xor {
-                println!("Expected _ XOR _ -> {name}");
-                expected_basic_add = None;
-                expected_prev_carry = None;
-                continue;
-            }
-
-            let WireSource::Gate(gate1) =
-                simulation.wire_sources.get(&gate.input1).unwrap()
-            else {
-                println!("Expected {name} to have gates nested");
-                expected_basic_add = None;
-                expected_prev_carry = None;
-                continue;
-            };
-            let WireSource::Gate(gate2) =
-                simulation.wire_sources.get(&gate.input2).unwrap()
-            else {
-                println!("Expected {name} to have gates nested");
-                expected_basic_add = None;
-                expected_prev_carry = None;
-                continue;
-            };
-            let (gate_add, gate_prev_carry) =
-                if gate1.gate_type == GateType::Xor {
-                    (gate1, gate2)
-                } else {
-                    (gate2, gate1)
-                };
-            let (gate_add_name, gate_prev_carry_name) =
-                if gate1.gate_type == GateType::Xor {
-                    (&gate.input1, &gate.input2)
-                } else {
-                    (&gate.input2, &gate.input1)
-                };
-            if let Some(ref expected_basic_add) = expected_basic_add {
-                if *gate_add_name != *expected_basic_add {
-                    println!(
-                        "Failed expectation from {} that {expected_basic_add} is the basic add for {z_num}",
-                        z_num + 1
-                    );
-                    continue;
-                }
-            }
-            if let Some(ref expected_prev_carry) = expected_prev_carry {
-                if *gate_prev_carry_name != *expected_prev_carry {
-                    println!(
-                        "Failed expectation from {} that {expected_prev_carry} is the previous carry for {z_num}",
-                        z_num + 1
-                    );
-                    continue;
-                }
-            }
-            expected_basic_add = None;
-            expected_prev_carry = None;
-            if gate_add.gate_type != GateType::Xor {
-                println!("Expected GA {gate_add_name} to be XOR");
-                continue;
-            }
-            if gate_prev_carry.gate_type != GateType::Or {
-                println!(
-                    "Expected GPC {gate_prev_carry_name} to be OR (on bit {z_num})"
-                );
-                continue;
-            }
-            if gate_add.input1 != x_name && gate_add.input2 != x_name {
-                println!("Expected GA {gate_add_name} to have {x_name} input");
-                continue;
-            }
-            if gate_add.input1 != y_name && gate_add.input2 != y_name {
-                println!("Expected GA {gate_add_name} to have {y_name} input");
-                continue;
-            }
-            let WireSource::Gate(gate3) = simulation
-                .wire_sources
-                .get(&gate_prev_carry.input1)
-                .unwrap()
-            else {
-                println!(
-                    "Expected GPC {gate_prev_carry_name} to have gates nested"
-                );
-                continue;
-            };
-            let WireSource::Gate(gate4) = simulation
-                .wire_sources
-                .get(&gate_prev_carry.input2)
-                .unwrap()
-            else {
-                println!(
-                    "Expected GPC {gate_prev_carry_name} to have gates nested"
-                );
-                continue;
-            };
-            let (gate_prev_basic_carry, gate_prev_fancy_carry) =
-                if gate3.input1.starts_with('x')
-                    || gate3.input1.starts_with('y')
-                {
-                    (gate3, gate4)
-                } else {
-                    (gate4, gate3)
-                };
-            let (gpbc_name, gpfc_name) = if gate3.input1.starts_with('x')
-                || gate3.input1.starts_with('y')
-            {
-                (&gate_prev_carry.input1, &gate_prev_carry.input2)
-            } else {
-                (&gate_prev_carry.input2, &gate_prev_carry.input1)
-            };
-            if gate_prev_basic_carry.gate_type != GateType::And {
-                println!(
-                    "Expected GPBC {gpbc_name} to be AND (on bit {z_num})"
-                );
-                continue;
-            }
-            if gate_prev_fancy_carry.gate_type != GateType::And {
-                println!(
-                    "Expected GPFC {gpfc_name} to be AND (on bit {z_num})"
-                );
-                continue;
-            }
-            let prev_x_name = format!("x{:02}", z_num - 1);
-            let prev_y_name = format!("y{:02}", z_num - 1);
-            if gate_prev_basic_carry.input1 != prev_x_name
-                && gate_prev_basic_carry.input2 != prev_x_name
-            {
-                println!(
-                    "Expected {gate_prev_basic_carry:?} to have {prev_x_name} input"
-                );
-                continue;
-            }
-            if gate_prev_basic_carry.input1 != prev_y_name
-                && gate_prev_basic_carry.input2 != prev_y_name
-            {
-                println!(
-                    "Expected {gate_prev_basic_carry:?} to have {prev_y_name} input"
-                );
-                continue;
-            }
-
-            let WireSource::Gate(gate5) = simulation
-                .wire_sources
-                .get(&gate_prev_fancy_carry.input1)
-                .unwrap()
-            else {
-                println!(
-                    "Expected {gate_prev_fancy_carry:?} to have nested gates"
-                );
-                continue;
-            };
-            if gate5.input1.ends_with(&format!("{:02}", z_num - 1)) {
-                expected_basic_add =
-                    Some(gate_prev_fancy_carry.input1.to_owned());
-                expected_prev_carry =
-                    Some(gate_prev_fancy_carry.input2.to_owned());
-            } else {
-                expected_prev_carry =
-                    Some(gate_prev_fancy_carry.input1.to_owned());
-                expected_basic_add =
-                    Some(gate_prev_fancy_carry.input2.to_owned());
-            }
+        Part::Part2 => {
+            println!("{}", simulation.repair_adder()?.join(","));
         }
     }
 
-    let result = simulation.z_number();
-
-    println!("{result}");
-
     Ok(())
 }
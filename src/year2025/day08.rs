@@ -4,7 +4,8 @@ use std::io;
 use std::mem::swap;
 
 use crate::errors::invalid_input;
-use crate::parse::{lines, parse_all};
+use crate::geometry::Point3D;
+use crate::parse::lines;
 use crate::part::Part;
 
 type Num = i64;
@@ -17,21 +18,6 @@ type Circuit = HashSet<BoxID>;
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
 struct CircuitID(usize);
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
-struct Position3D {
-    pub x: Num,
-    pub y: Num,
-    pub z: Num,
-}
-
-impl Position3D {
-    pub fn squared_dist(self, other: Self) -> Num {
-        (self.x - other.x).pow(2)
-            + (self.y - other.y).pow(2)
-            + (self.z - other.z).pow(2)
-    }
-}
-
 #[derive(Clone, PartialEq, Eq)]
 struct HeapEntry {
     squared_distance: Num,
@@ -52,7 +38,7 @@ impl PartialOrd for HeapEntry {
 }
 
 struct Playground {
-    box_positions: Vec<Position3D>,
+    box_positions: Vec<Point3D>,
     shortest_maybe_unconnected: BinaryHeap<HeapEntry>,
     circuits: Vec<Circuit>,
     num_circuits: usize,
@@ -69,13 +55,7 @@ enum ConnectResult {
 impl Playground {
     fn new<R: io::Read>(reader: io::BufReader<R>) -> io::Result<Self> {
         let box_positions = lines(reader)?
-            .map(|line| {
-                let components = parse_all::<_, Num>(line.split(','))?;
-                let &[x, y, z] = &components[..] else {
-                    return Err(invalid_input("Expected x,y,z coordinate"));
-                };
-                Ok(Position3D { x, y, z })
-            })
+            .map(|line| line.parse::<Point3D>())
             .collect::<io::Result<Vec<_>>>()?;
 
         if box_positions.len() <= 1 {
@@ -89,7 +69,7 @@ impl Playground {
         for i in 0..box_positions.len() {
             for j in i + 1..box_positions.len() {
                 let squared_distance =
-                    box_positions[i].squared_dist(box_positions[j]);
+                    box_positions[i].squared_distance(box_positions[j]);
                 let entry = HeapEntry {
                     squared_distance,
                     id_1: BoxID(i),
@@ -176,7 +156,7 @@ impl Playground {
         }
     }
 
-    fn box_position(&self, id: BoxID) -> Option<Position3D> {
+    fn box_position(&self, id: BoxID) -> Option<Point3D> {
         self.box_positions.get(id.0).map(|position| *position)
     }
 }
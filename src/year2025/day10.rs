@@ -1,8 +1,11 @@
-use std::cmp::Reverse;
 use std::io;
 use std::io::BufRead;
 
+use num_rational::Ratio;
+
 use crate::errors::invalid_input;
+use crate::ilp::{minimize_sum, press_upper_bounds};
+use crate::matrix::Matrix;
 use crate::parse::parse_all;
 use crate::part::Part;
 
@@ -21,27 +24,6 @@ fn xor(vec1: &mut BitSet, vec2: &BitSet) {
     }
 }
 
-fn add(arr1: &mut JoltageArray, arr2: &JoltageArray) {
-    for i in 0..MAX_NUM_JOLTAGES {
-        arr1[i] += arr2[i];
-    }
-}
-
-fn add_mult(arr1: &mut JoltageArray, arr2: &JoltageArray, mult: Joltage) {
-    for i in 0..MAX_NUM_JOLTAGES {
-        arr1[i] += mult * arr2[i];
-    }
-}
-
-fn any_greater(arr1: &JoltageArray, arr2: &JoltageArray) -> bool {
-    for i in 0..MAX_NUM_JOLTAGES {
-        if arr1[i] > arr2[i] {
-            return true;
-        }
-    }
-    false
-}
-
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 struct LightingSearchState {
     buttons_considered: usize,
@@ -49,13 +31,6 @@ struct LightingSearchState {
     lights: BitSet,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct JoltageSearchState {
-    button_presses: usize,
-    next_button: usize,
-    joltages: JoltageArray,
-}
-
 struct Machine {
     lighting_diagram: BitSet,
     lighting_buttons: Vec<BitSet>,
@@ -195,91 +170,19 @@ impl Machine {
     }
 
     fn fewest_button_presses_joltage(&self) -> Option<usize> {
-        let mut buttons = self.joltage_buttons.clone();
-        buttons.sort_by_key(|button| Reverse(button.iter().sum::<Joltage>()));
-
-        let mut button_id_to_last_joltage_ids =
-            vec![vec![0usize; 0]; buttons.len()];
-        {
-            let mut taken = [false; MAX_NUM_JOLTAGES];
-            for (button_id, button) in buttons.iter().enumerate().rev() {
-                for joltage_id in 0..MAX_NUM_JOLTAGES {
-                    if button[joltage_id] == 1 && !taken[joltage_id] {
-                        button_id_to_last_joltage_ids[button_id]
-                            .push(joltage_id);
-                        taken[joltage_id] = true;
-                    }
-                }
-            }
-        }
-
-        let mut best_result = usize::MAX;
-        let mut states = Vec::<JoltageSearchState>::new();
-        states.push(JoltageSearchState {
-            button_presses: 0,
-            next_button: 0,
-            joltages: [0; MAX_NUM_JOLTAGES],
-        });
-
-        while let Some(mut state) = states.pop() {
-            if state.button_presses >= best_result {
-                continue;
-            }
-            if any_greater(&state.joltages, &self.joltages) {
-                continue;
-            }
-            if state.next_button >= buttons.len() {
-                continue;
-            }
-            if state.joltages == self.joltages {
-                if state.button_presses < best_result {
-                    best_result = state.button_presses;
-                }
-                continue;
-            }
-
-            let last_joltage_ids =
-                &button_id_to_last_joltage_ids[state.next_button];
-            if !last_joltage_ids.is_empty() {
-                for &joltage_id in last_joltage_ids {
-                    let diff =
-                        self.joltages[joltage_id] - state.joltages[joltage_id];
-                    state.button_presses += diff as usize;
-                    add_mult(
-                        &mut state.joltages,
-                        &buttons[state.next_button],
-                        diff,
-                    );
-                }
-                state.next_button += 1;
-                if state.next_button == buttons.len() {
-                    if state.joltages == self.joltages {
-                        if state.button_presses < best_result {
-                            best_result = state.button_presses;
-                        }
-                    }
-                } else {
-                    states.push(state);
-                }
-                continue;
-            }
-
-            let mut state_move = state.clone();
-            state_move.next_button += 1;
-            states.push(state_move);
-
-            state.button_presses += 1;
-            add(&mut state.joltages, &buttons[state.next_button]);
-            states.push(state);
-        }
-
-        // Uncomment for intermediate results:
-        // println!("Final result: {best_result}");
-        if best_result == usize::MAX {
-            None
-        } else {
-            Some(best_result)
-        }
+        let coefficients = Matrix::from_rows((0..MAX_NUM_JOLTAGES).map(|i| {
+            self.joltage_buttons
+                .iter()
+                .map(move |button| Ratio::from_integer(button[i] as i64))
+        }));
+        let target = self
+            .joltages
+            .iter()
+            .map(|&joltage| joltage as i64)
+            .collect::<Vec<_>>();
+        let upper_bounds = press_upper_bounds(&coefficients, &target);
+        minimize_sum(&coefficients, &target, &upper_bounds)
+            .map(|presses| presses as usize)
     }
 }
 
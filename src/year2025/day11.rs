@@ -1,20 +1,17 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::io;
 
-use crate::errors::invalid_input;
+use crate::graph::Graph;
+use crate::memo::Memo;
 use crate::parse::lines;
 use crate::part::Part;
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-struct DeviceID(String);
-
-struct Device {
-    connections: Vec<DeviceID>,
-}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct DeviceID(usize);
 
 struct ServerRack {
-    devices: HashMap<DeviceID, Device>,
-    predecessors: HashMap<DeviceID, HashSet<DeviceID>>,
+    connections: Graph,
+    predecessors: Graph,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -31,7 +28,7 @@ impl ResultsTableKey {
         passing_through: &HashSet<DeviceID>,
     ) -> Self {
         let mut passing_through_vec =
-            passing_through.iter().cloned().collect::<Vec<_>>();
+            passing_through.iter().copied().collect::<Vec<_>>();
         passing_through_vec.sort();
         Self {
             start,
@@ -43,83 +40,62 @@ impl ResultsTableKey {
 
 impl ServerRack {
     fn new<R: io::Read>(reader: io::BufReader<R>) -> io::Result<Self> {
-        let devices = lines(reader)?
-            .map(|line| {
-                let &[device_name, connections_str] =
-                    &line.split(": ").collect::<Vec<_>>()[..]
-                else {
-                    return Err(invalid_input("Expected name: connections"));
-                };
-                let connections = connections_str
-                    .split(' ')
-                    .map(|string| DeviceID(string.to_owned()))
-                    .collect::<Vec<_>>();
-                Ok((DeviceID(device_name.to_owned()), Device { connections }))
-            })
-            .collect::<io::Result<HashMap<_, _>>>()?;
-
-        let mut predecessors = HashMap::<DeviceID, HashSet<DeviceID>>::new();
-        for (device_id, device) in devices.iter() {
-            for output in device.connections.iter() {
-                predecessors
-                    .entry(output.clone())
-                    .or_default()
-                    .insert(device_id.clone());
-            }
-        }
+        let connections = Graph::from_adjacency_lines(lines(reader)?, true)?;
+        let predecessors = connections.reversed();
 
         Ok(Self {
-            devices,
+            connections,
             predecessors,
         })
     }
 
+    fn device_id(&self, name: &str) -> Option<DeviceID> {
+        self.connections.id(name).map(DeviceID)
+    }
+
     fn num_paths_between(
         &self,
-        start: &DeviceID,
-        end: &DeviceID,
-        passing_through: &HashSet<DeviceID>,
+        start: &str,
+        end: &str,
+        passing_through: &[&str],
     ) -> usize {
-        if !self.devices.contains_key(start)
-            || !self.predecessors.contains_key(end)
-        {
+        let (Some(start), Some(end)) =
+            (self.device_id(start), self.device_id(end))
+        else {
             return 0;
-        }
-        for id in passing_through.iter() {
-            if !self.devices.contains_key(id) {
-                return 0;
-            }
-        }
+        };
+        let Some(passing_through) = passing_through
+            .iter()
+            .map(|name| self.device_id(name))
+            .collect::<Option<HashSet<_>>>()
+        else {
+            return 0;
+        };
 
-        let mut map = HashMap::new();
-        self.num_paths_between_helper(start, end, passing_through, &mut map)
+        let mut memo = Memo::new();
+        self.num_paths_between_helper(start, end, &passing_through, &mut memo)
     }
 
     fn num_paths_between_helper(
         &self,
-        start: &DeviceID,
-        end: &DeviceID,
+        start: DeviceID,
+        end: DeviceID,
         passing_through: &HashSet<DeviceID>,
-        results_table: &mut HashMap<ResultsTableKey, usize>,
+        results_table: &mut Memo<ResultsTableKey, usize>,
     ) -> usize {
-        let key =
-            ResultsTableKey::new(start.clone(), end.clone(), passing_through);
-        if let Some(&result) = results_table.get(&key) {
-            return result;
+        if start == end && passing_through.is_empty() {
+            return 1;
         }
 
-        let result: usize = if start == end && passing_through.is_empty() {
-            if passing_through.is_empty() { 1 } else { 0 }
-        } else if !self.predecessors.contains_key(end) {
-            0
-        } else {
+        let key = ResultsTableKey::new(start, end, passing_through);
+        results_table.get_or_compute(key, |results_table| {
             self.predecessors
-                .get(end)
-                .unwrap()
+                .neighbors(end.0)
                 .iter()
-                .map(|predecessor_id| {
+                .map(|&predecessor| {
+                    let predecessor_id = DeviceID(predecessor);
                     let mut new_passing_through = passing_through.clone();
-                    new_passing_through.remove(predecessor_id);
+                    new_passing_through.remove(&predecessor_id);
                     self.num_paths_between_helper(
                         start,
                         predecessor_id,
@@ -128,11 +104,7 @@ impl ServerRack {
                     )
                 })
                 .sum()
-        };
-
-        results_table.insert(key, result);
-
-        result
+        })
     }
 }
 
@@ -146,14 +118,7 @@ pub fn run<R: io::Read>(
         Part::Part1 => ("you", "out", vec![]),
         Part::Part2 => ("svr", "out", vec!["dac", "fft"]),
     };
-    let result = server_rack.num_paths_between(
-        &DeviceID(start.to_owned()),
-        &DeviceID(end.to_owned()),
-        &passing_through
-            .into_iter()
-            .map(|id| DeviceID(id.to_owned()))
-            .collect(),
-    );
+    let result = server_rack.num_paths_between(start, end, &passing_through);
     println!("{result}");
 
     Ok(())
@@ -1,5 +1,6 @@
 use std::io;
 
+use crate::branch_and_bound::{BranchAndBound, Problem};
 use crate::errors::invalid_input;
 use crate::parse::{lines, paragraphs, parse_all, parse_words};
 use crate::part::Part;
@@ -76,12 +77,13 @@ struct PackingInfo {
     position: Position,
 }
 
+#[derive(Clone, Debug)]
 struct TreeState {
     left_to_pack: Vec<usize>,
+    last_packed: Option<PackingInfo>,
     min_tiles_required: usize,
     tiles_available: usize,
     occupied: Vec<Vec<bool>>,
-    packed: Vec<PackingInfo>,
 }
 
 impl TreeState {
@@ -91,7 +93,6 @@ impl TreeState {
         left_to_pack: Vec<usize>,
         presents: &Vec<Present>,
     ) -> Self {
-        let to_pack_count = left_to_pack.iter().sum::<usize>();
         let min_tiles_required = left_to_pack
             .iter()
             .zip(presents)
@@ -99,10 +100,10 @@ impl TreeState {
             .sum::<usize>();
         Self {
             left_to_pack,
+            last_packed: None,
             min_tiles_required,
             tiles_available: width * height,
             occupied: vec![vec![false; width]; height],
-            packed: Vec::with_capacity(to_pack_count),
         }
     }
 
@@ -129,54 +130,39 @@ impl TreeState {
         true
     }
 
-    fn pack(
-        &mut self,
+    // Calls `on_tile` with every tile the layout covers at the position
+    fn for_each_tile(
+        layout: &[[bool; 3]; 3],
         position: Position,
-        presents: &Vec<Present>,
-        packing_info: PackingInfo,
+        mut on_tile: impl FnMut(usize, usize),
     ) {
-        let present = &presents[packing_info.present_id];
-        let layout = &present.layouts[packing_info.orientation];
         for row_offset in 0..3 {
             for col_offset in 0..3 {
-                if !layout[row_offset][col_offset] {
-                    continue;
+                if layout[row_offset][col_offset] {
+                    on_tile(
+                        position.row + row_offset,
+                        position.col + col_offset,
+                    );
                 }
-
-                let row = position.row + row_offset;
-                let col = position.col + col_offset;
-                assert!(!self.occupied[row][col]);
-                self.occupied[row][col] = true;
             }
         }
-
-        self.left_to_pack[packing_info.present_id] -= 1;
-        self.min_tiles_required -= present.num_tiles_occupied;
-        self.tiles_available -= present.num_tiles_occupied;
-        self.packed.push(packing_info);
     }
 
-    fn unpack(&mut self, presents: &Vec<Present>) {
-        let packing_info = self.packed.pop().unwrap();
+    fn pack(&mut self, presents: &Vec<Present>, packing_info: PackingInfo) {
         let present = &presents[packing_info.present_id];
         let layout = &present.layouts[packing_info.orientation];
-        for row_offset in 0..3 {
-            for col_offset in 0..3 {
-                if !layout[row_offset][col_offset] {
-                    continue;
-                }
+        Self::for_each_tile(layout, packing_info.position, |row, col| {
+            assert!(!self.occupied[row][col]);
+            self.occupied[row][col] = true;
+        });
 
-                let row = packing_info.position.row + row_offset;
-                let col = packing_info.position.col + col_offset;
-                assert!(self.occupied[row][col]);
-                self.occupied[row][col] = false;
-            }
-        }
-        self.left_to_pack[packing_info.present_id] += 1;
-        self.min_tiles_required += present.num_tiles_occupied;
-        self.tiles_available += present.num_tiles_occupied;
+        self.left_to_pack[packing_info.present_id] -= 1;
+        self.last_packed = Some(packing_info);
+        self.min_tiles_required -= present.num_tiles_occupied;
+        self.tiles_available -= present.num_tiles_occupied;
     }
 }
+
 #[derive(Clone, Debug)]
 struct TreeSpace {
     width: usize,
@@ -205,61 +191,139 @@ impl TreeSpace {
     }
 
     fn can_be_packed(&self, presents: &Vec<Present>) -> bool {
-        self.can_be_packed_helper(
+        let packer = Packer {
+            space: self,
             presents,
-            &mut TreeState::new(
-                self.width,
-                self.height,
-                self.present_amounts.clone(),
-                presents,
-            ),
-        )
+        };
+        let state = TreeState::new(
+            self.width,
+            self.height,
+            self.present_amounts.clone(),
+            presents,
+        );
+        BranchAndBound::new(&packer)
+            .maximize(state)
+            .best
+            .unwrap_or(false)
+    }
+}
+
+struct Packer<'a> {
+    space: &'a TreeSpace,
+    presents: &'a Vec<Present>,
+}
+
+impl Packer<'_> {
+    // The free tiles that some present still to be packed could cover
+    fn num_usable_tiles(&self, state: &TreeState) -> usize {
+        let mut usable = vec![vec![false; self.space.width]; self.space.height];
+        for (present, _) in self
+            .presents
+            .iter()
+            .zip(&state.left_to_pack)
+            .filter(|&(_, &count)| count > 0)
+        {
+            for row in 0..self.space.height {
+                for col in 0..self.space.width {
+                    let position = Position { row, col };
+                    for layout in &present.layouts {
+                        if state.can_fit(layout, position) {
+                            TreeState::for_each_tile(
+                                layout,
+                                position,
+                                |row, col| usable[row][col] = true,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        usable
+            .iter()
+            .flatten()
+            .filter(|&&is_usable| is_usable)
+            .count()
     }
+}
 
-    fn can_be_packed_helper(
-        &self,
-        presents: &Vec<Present>,
-        state: &mut TreeState,
-    ) -> bool {
+// Finding any packing is enough, so the objective is whether everything is
+// packed, and the bound is whether everything still might be. Once a packing
+// is found the rest of the search is pruned.
+impl Problem for Packer<'_> {
+    type State = TreeState;
+    type Move = PackingInfo;
+    type Value = bool;
+
+    fn moves(&self, state: &TreeState) -> Vec<PackingInfo> {
         let Some((next_present_id, _)) = state
             .left_to_pack
             .iter()
             .copied()
             .enumerate()
-            .filter(|&(_, count)| count > 0)
-            .next()
+            .find(|&(_, count)| count > 0)
         else {
-            return true;
+            return Vec::new();
         };
 
-        if state.min_tiles_required > state.tiles_available {
-            return false;
-        }
-
-        let present = &presents[next_present_id];
-
-        for row in 0..self.height {
-            for col in 0..self.width {
+        // Copies of a present are packed one after another, and trying them
+        // in every order would be wasted work, so each copy goes after the
+        // last one
+        let key = |packing_info: &PackingInfo| {
+            (
+                packing_info.position.row,
+                packing_info.position.col,
+                packing_info.orientation,
+            )
+        };
+        let previous_copy = state
+            .last_packed
+            .filter(|last_packed| last_packed.present_id == next_present_id);
+
+        let present = &self.presents[next_present_id];
+        let mut moves = Vec::new();
+        for row in 0..self.space.height {
+            for col in 0..self.space.width {
                 let position = Position { row, col };
                 for orientation in 0..8 {
+                    let packing_info = PackingInfo {
+                        present_id: next_present_id,
+                        orientation,
+                        position,
+                    };
+                    if previous_copy.is_some_and(|previous_copy| {
+                        key(&packing_info) <= key(&previous_copy)
+                    }) {
+                        continue;
+                    }
                     let layout = &present.layouts[orientation];
                     if state.can_fit(layout, position) {
-                        let packing_info = PackingInfo {
-                            present_id: next_present_id,
-                            orientation,
-                            position,
-                        };
-                        state.pack(position, presents, packing_info);
-                        if self.can_be_packed_helper(presents, state) {
-                            return true;
-                        }
-                        state.unpack(presents);
+                        moves.push(packing_info);
                     }
                 }
             }
         }
+        moves
+    }
+
+    fn apply(
+        &self,
+        state: &TreeState,
+        packing_info: &PackingInfo,
+    ) -> TreeState {
+        let mut state = state.clone();
+        state.pack(self.presents, *packing_info);
+        state
+    }
+
+    fn objective(&self, state: &TreeState) -> Option<bool> {
+        Some(state.left_to_pack.iter().all(|&count| count == 0))
+    }
 
-        false
+    // The presents still to be packed need at least as many tiles as they
+    // have, and only tiles that one of them could cover are any use
+    fn bound(&self, state: &TreeState) -> bool {
+        state.min_tiles_required <= state.tiles_available
+            && state.min_tiles_required <= self.num_usable_tiles(state)
     }
 }
 